@@ -3,8 +3,14 @@ use std::cmp::Ordering;
 use std::fmt;
 use std::io::IpAddr as StdIpAddr;
 use std::ops::*;
+use std::num::Int;
 use std::str::FromStr;
+use std::u32;
+use super::IpAddress;
 use super::IpAddrVersion::{self, Ipv4};
+use super::ipv6;
+use eui::Eui48;
+use wire::WireError;
 
 pub const MAX_PREFIXLEN: uint = 32;
 
@@ -29,9 +35,10 @@ impl IpAddr {
     /// The provided prefixlen must be in the prefixlen-range (`0` <= `n` <= `32`).
     pub fn with_prefixlen(n: uint) -> IpAddr {
         assert!(n <= MAX_PREFIXLEN);
-        let mask: u32 = (1 << n) - 1;
-        let mask = mask << (MAX_PREFIXLEN - n);
-        IpAddr::from_u32(mask)
+        if n == 0 {
+            return IpAddr::from_u32(0);
+        }
+        IpAddr::from_u32(!0 << (MAX_PREFIXLEN - n))
     }
 
     /// The binary representation of this address - a bytes vector of the appropriate length (most significant octet first).
@@ -59,6 +66,39 @@ impl IpAddr {
     }
 }
 
+impl IpAddress for IpAddr {
+    fn version(&self) -> IpAddrVersion {
+        Ipv4
+    }
+
+    fn bit_width() -> uint {
+        MAX_PREFIXLEN
+    }
+
+    fn with_prefixlen(n: uint) -> IpAddr {
+        IpAddr::with_prefixlen(n)
+    }
+
+    fn from_u128(n: [u64; 2]) -> IpAddr {
+        IpAddr::from_u32(n[1] as u32)
+    }
+
+    fn to_u128(&self) -> [u64; 2] {
+        [0, self.to_u32() as u64]
+    }
+
+    fn checked_add(&self, n: u64) -> Option<IpAddr> {
+        match (self.to_u32() as u64).checked_add(n) {
+            Some(n) if n <= u32::MAX as u64 => Some(IpAddr::from_u32(n as u32)),
+            _ => None,
+        }
+    }
+
+    fn checked_sub(&self, n: u64) -> Option<IpAddr> {
+        (self.to_u32() as u64).checked_sub(n).map(|n| IpAddr::from_u32(n as u32))
+    }
+}
+
 impl Add<u32> for IpAddr {
     type Output = Self;
 
@@ -119,6 +159,103 @@ impl Ord for IpAddr {
     }
 }
 
+///! Netmasks and host (wildcard) masks.
+impl IpAddr {
+    /// Get the prefix length of this netmask, or `None` if its bits aren't contiguous
+    /// (the inverse of `with_prefixlen`).
+    pub fn prefix_len_from_mask(&self) -> Option<uint> {
+        let n = self.to_u32().count_ones();
+        if IpAddr::with_prefixlen(n) == *self { Some(n) } else { None }
+    }
+
+    /// `true` if this address is a netmask: ones followed by zeros (e.g. `255.255.255.0`).
+    pub fn is_netmask(&self) -> bool {
+        self.prefix_len_from_mask().is_some()
+    }
+
+    /// `true` if this address is a host mask: zeros followed by ones (e.g. `0.0.0.255`).
+    pub fn is_hostmask(&self) -> bool {
+        (!*self).is_netmask()
+    }
+
+    /// Convert this netmask to the corresponding host mask, or `None` if it isn't a netmask.
+    pub fn to_hostmask(&self) -> Option<IpAddr> {
+        if self.is_netmask() { Some(!*self) } else { None }
+    }
+
+    /// Convert this host mask to the corresponding netmask, or `None` if it isn't a host mask.
+    pub fn to_netmask(&self) -> Option<IpAddr> {
+        if self.is_hostmask() { Some(!*self) } else { None }
+    }
+}
+
+///! Binary encoding, in network byte order.
+impl IpAddr {
+    /// Get the octets of this address, most significant first.
+    pub fn octets(&self) -> [u8; 4] {
+        self.packed()
+    }
+
+    /// Create an `IpAddr` instance from exactly 4 octets, most significant first.
+    pub fn from_bytes(b: &[u8]) -> Result<IpAddr, WireError> {
+        if b.len() != 4 {
+            return Err(WireError::InvalidLength(b.len()));
+        }
+        Ok(IpAddr(b[0], b[1], b[2], b[3]))
+    }
+
+    /// Write the octets of this address at the start of `buf`, returning the number of bytes written.
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<uint, WireError> {
+        if buf.len() < 4 {
+            return Err(WireError::BufferTooShort);
+        }
+        for (dst, src) in buf.iter_mut().zip(self.octets().iter()) {
+            *dst = *src;
+        }
+        Ok(4)
+    }
+}
+
+///! Convert operations from/to IPv6 addresses embedding an IPv4 address.
+impl IpAddr {
+    /// Create the IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) of this address.
+    pub fn to_ipv6_mapped(&self) -> ipv6::IpAddr {
+        let (hi, lo) = self.to_u16_pair();
+        ipv6::IpAddr(0, 0, 0, 0, 0, 0xffff, hi, lo)
+    }
+
+    /// Create the deprecated IPv4-compatible IPv6 address (`::a.b.c.d`) of this address.
+    pub fn to_ipv6_compatible(&self) -> ipv6::IpAddr {
+        let (hi, lo) = self.to_u16_pair();
+        ipv6::IpAddr(0, 0, 0, 0, 0, 0, hi, lo)
+    }
+
+    fn to_u16_pair(&self) -> (u16, u16) {
+        let n = self.to_u32();
+        ((n >> 16) as u16, n as u16)
+    }
+}
+
+///! Multicast addresses (RFC 5771).
+impl IpAddr {
+    /// `true` if this address is a multicast address (`224.0.0.0/4`).
+    pub fn is_multicast(&self) -> bool {
+        let &IpAddr(a, _, _, _) = self;
+        a >> 4 == 0xe
+    }
+
+    /// Get the Ethernet multicast address this multicast address is mapped to (`01:00:5e`
+    /// followed by the 23 lowest bits of the address, RFC 1112).
+    pub fn multicast_mac(&self) -> Option<Eui48> {
+        let &IpAddr(_, b, c, d) = self;
+        if self.is_multicast() {
+            Some(Eui48(0x01, 0x00, 0x5e, b & 0x7f, c, d))
+        } else {
+            None
+        }
+    }
+}
+
 ///! Convert operations from/to Rust's standard library IP addresses.
 impl IpAddr {
     /// Create an `ipv4::IpAddr` instance from a Rust's standard library `IpAddr` instance.
@@ -159,6 +296,7 @@ impl FromStr for IpAddr {
 #[cfg(test)]
 mod test {
     use super::IpAddr;
+    use addr::IpAddress;
 
     #[test]
     fn test_num() {
@@ -167,6 +305,11 @@ mod test {
 
         assert_eq!(IpAddr(127, 0, 0, 2) - 1, IpAddr(127, 0, 0 ,1));
         assert_eq!(IpAddr(127, 0, 1, 0) - 1, IpAddr(127, 0, 0, 255));
+
+        assert_eq!(IpAddr(127, 0, 0, 255).checked_add(1), Some(IpAddr(127, 0, 1, 0)));
+        assert_eq!(IpAddr(255, 255, 255, 255).checked_add(1), None);
+        assert_eq!(IpAddr(0, 0, 0, 1).checked_sub(1), Some(IpAddr(0, 0, 0, 0)));
+        assert_eq!(IpAddr(0, 0, 0, 1).checked_sub(2), None);
     }
 
     #[test]
@@ -179,9 +322,69 @@ mod test {
         assert_eq!(!mask, IpAddr(0, 0, 0, 255));
     }
 
+    #[test]
+    fn test_ipv6_embedding() {
+        use addr::ipv6;
+
+        let ip = IpAddr(192, 0, 2, 33);
+        assert_eq!(ip.to_ipv6_mapped(), ipv6::IpAddr(0, 0, 0, 0, 0, 0xffff, 0xc000, 0x221));
+        assert_eq!(ip.to_ipv6_compatible(), ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0xc000, 0x221));
+        assert_eq!(ip.to_ipv6_mapped().to_ipv4_mapped(), Some(ip));
+    }
+
+    #[test]
+    fn test_multicast() {
+        use eui::Eui48;
+
+        assert!(IpAddr(224, 0, 0, 251).is_multicast());
+        assert!(IpAddr(239, 255, 255, 250).is_multicast());
+        assert!(!IpAddr(192, 0, 2, 1).is_multicast());
+        assert!(!IpAddr(240, 0, 0, 1).is_multicast());
+
+        assert_eq!(IpAddr(224, 0, 0, 251).multicast_mac(), Some(Eui48(0x01, 0x00, 0x5e, 0x00, 0x00, 0xfb)));
+        assert_eq!(IpAddr(239, 255, 255, 250).multicast_mac(), Some(Eui48(0x01, 0x00, 0x5e, 0x7f, 0xff, 0xfa)));
+        assert_eq!(IpAddr(192, 0, 2, 1).multicast_mac(), None);
+    }
+
+    #[test]
+    fn test_bytes() {
+        use wire::WireError;
+
+        let ip = IpAddr(192, 0, 2, 1);
+        assert_eq!(ip.octets(), [192, 0, 2, 1]);
+        assert_eq!(IpAddr::from_bytes(&[192, 0, 2, 1]), Ok(ip));
+        assert_eq!(IpAddr::from_bytes(&[192, 0, 2]), Err(WireError::InvalidLength(3)));
+        assert_eq!(IpAddr::from_bytes(&[192, 0, 2, 1, 0]), Err(WireError::InvalidLength(5)));
+
+        let mut buf = [0u8; 6];
+        assert_eq!(ip.write_to(&mut buf), Ok(4));
+        assert_eq!(buf, [192, 0, 2, 1, 0, 0]);
+        assert_eq!(ip.write_to(&mut buf[..3]), Err(WireError::BufferTooShort));
+    }
+
     #[test]
     fn test_prefixlen() {
         assert_eq!(IpAddr::with_prefixlen(16), IpAddr(255, 255, 0, 0));
         assert_eq!(IpAddr::with_prefixlen(24), IpAddr(255, 255, 255, 0));
+        assert_eq!(IpAddr::with_prefixlen(0), IpAddr(0, 0, 0, 0));
+        assert_eq!(IpAddr::with_prefixlen(32), IpAddr(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn test_masks() {
+        assert_eq!(IpAddr(255, 255, 255, 0).prefix_len_from_mask(), Some(24));
+        assert_eq!(IpAddr(0, 0, 0, 0).prefix_len_from_mask(), Some(0));
+        assert_eq!(IpAddr(255, 255, 255, 255).prefix_len_from_mask(), Some(32));
+        assert_eq!(IpAddr(255, 0, 255, 0).prefix_len_from_mask(), None);
+        assert_eq!(IpAddr(0, 0, 0, 255).prefix_len_from_mask(), None);
+
+        assert!(IpAddr(255, 255, 128, 0).is_netmask() && !IpAddr(255, 255, 128, 0).is_hostmask());
+        assert!(IpAddr(0, 0, 127, 255).is_hostmask() && !IpAddr(0, 0, 127, 255).is_netmask());
+        assert!(IpAddr(0, 0, 0, 0).is_netmask() && IpAddr(0, 0, 0, 0).is_hostmask());
+
+        assert_eq!(IpAddr(255, 255, 255, 0).to_hostmask(), Some(IpAddr(0, 0, 0, 255)));
+        assert_eq!(IpAddr(0, 0, 0, 255).to_netmask(), Some(IpAddr(255, 255, 255, 0)));
+        assert_eq!(IpAddr(0, 0, 0, 255).to_hostmask(), None);
+        assert_eq!(IpAddr(255, 0, 255, 0).to_netmask(), None);
     }
 }
@@ -4,8 +4,13 @@ use std::fmt;
 use std::io::IpAddr as StdIpAddr;
 use std::ops::*;
 use std::simd::u64x2;
+use std::num::Int;
 use std::str::FromStr;
+use super::IpAddress;
 use super::IpAddrVersion::{self, Ipv6};
+use super::ipv4;
+use eui::{Eui48, Eui64};
+use wire::WireError;
 
 pub const MAX_PREFIXLEN: uint = 128;
 
@@ -30,20 +35,11 @@ impl IpAddr {
     /// The provided prefixlen must be in the prefixlen-range (`0` <= `n` <= `128`).
     pub fn with_prefixlen(n: uint) -> IpAddr {
         assert!(n <= MAX_PREFIXLEN);
-        let mid = MAX_PREFIXLEN / 2;
-        if n >= mid {
-            let n = n - mid;
-
-            let hi = ::std::u64::MAX;
-            let lo = (1 << n) - 1;
-            let lo = lo << (mid - n);
-
-            IpAddr::from_simd(u64x2(hi, lo))
+        let mask = |n: uint| if n == 0 { 0 } else { !0u64 << (64 - n) };
+        if n >= 64 {
+            IpAddr::from_simd(u64x2(!0, mask(n - 64)))
         } else {
-            let hi = (1 << n) - 1;
-            let hi = hi << (MAX_PREFIXLEN - n);
-
-            IpAddr::from_simd(u64x2(hi, 0))
+            IpAddr::from_simd(u64x2(mask(n), 0))
         }
     }
 
@@ -71,6 +67,12 @@ impl IpAddr {
         ]
     }
 
+    /// Create an `IpAddr` instance from its binary representation (most significant octet first).
+    pub fn from_packed(b: [u8; 16]) -> IpAddr {
+        let word = |i: uint| ((b[i] as u16) << 8) | b[i + 1] as u16;
+        IpAddr(word(0), word(2), word(4), word(6), word(8), word(10), word(12), word(14))
+    }
+
     /// Create an `IpAddr` instance from a 128-bits integer.
     ///
     /// As Rust doesn't support u128-bits integer natively, this
@@ -113,6 +115,44 @@ impl IpAddr {
     }
 }
 
+impl IpAddress for IpAddr {
+    fn version(&self) -> IpAddrVersion {
+        Ipv6
+    }
+
+    fn bit_width() -> uint {
+        MAX_PREFIXLEN
+    }
+
+    fn with_prefixlen(n: uint) -> IpAddr {
+        IpAddr::with_prefixlen(n)
+    }
+
+    fn from_u128(n: [u64; 2]) -> IpAddr {
+        IpAddr::from_u128(n)
+    }
+
+    fn to_u128(&self) -> [u64; 2] {
+        IpAddr::to_u128(self)
+    }
+
+    fn checked_add(&self, n: u64) -> Option<IpAddr> {
+        let [hi, lo] = self.to_u128();
+        match lo.checked_add(n) {
+            Some(lo) => Some(IpAddr::from_u128([hi, lo])),
+            None => hi.checked_add(1).map(|hi| IpAddr::from_u128([hi, lo.wrapping_add(n)])),
+        }
+    }
+
+    fn checked_sub(&self, n: u64) -> Option<IpAddr> {
+        let [hi, lo] = self.to_u128();
+        match lo.checked_sub(n) {
+            Some(lo) => Some(IpAddr::from_u128([hi, lo])),
+            None => hi.checked_sub(1).map(|hi| IpAddr::from_u128([hi, lo.wrapping_sub(n)])),
+        }
+    }
+}
+
 impl Add<u64> for IpAddr {
     type Output = Self;
 
@@ -134,10 +174,10 @@ impl Sub<u64> for IpAddr {
     fn sub(self, rhs: u64) -> IpAddr {
         let u64x2(mut hi, mut lo) = self.to_simd();
 
-        lo -= rhs;
-        if lo > rhs {
+        if lo < rhs {
             hi -= 1;
         }
+        lo -= rhs;
 
         IpAddr::from_simd(u64x2(hi, lo))
     }
@@ -191,6 +231,285 @@ impl Ord for IpAddr {
     }
 }
 
+///! Netmasks and host (wildcard) masks.
+impl IpAddr {
+    /// Get the prefix length of this netmask, or `None` if its bits aren't contiguous
+    /// (the inverse of `with_prefixlen`).
+    pub fn prefix_len_from_mask(&self) -> Option<uint> {
+        let [hi, lo] = self.to_u128();
+        let n = hi.count_ones() + lo.count_ones();
+        if IpAddr::with_prefixlen(n) == *self { Some(n) } else { None }
+    }
+
+    /// `true` if this address is a netmask: ones followed by zeros (e.g. `ffff:ffff::`).
+    pub fn is_netmask(&self) -> bool {
+        self.prefix_len_from_mask().is_some()
+    }
+
+    /// `true` if this address is a host mask: zeros followed by ones (e.g. `::ffff`).
+    pub fn is_hostmask(&self) -> bool {
+        (!*self).is_netmask()
+    }
+
+    /// Convert this netmask to the corresponding host mask, or `None` if it isn't a netmask.
+    pub fn to_hostmask(&self) -> Option<IpAddr> {
+        if self.is_netmask() { Some(!*self) } else { None }
+    }
+
+    /// Convert this host mask to the corresponding netmask, or `None` if it isn't a host mask.
+    pub fn to_netmask(&self) -> Option<IpAddr> {
+        if self.is_hostmask() { Some(!*self) } else { None }
+    }
+}
+
+///! Binary encoding, in network byte order.
+impl IpAddr {
+    /// Get the octets of this address, most significant first.
+    pub fn octets(&self) -> [u8; 16] {
+        self.packed()
+    }
+
+    /// Create an `IpAddr` instance from exactly 16 octets, most significant first.
+    pub fn from_bytes(b: &[u8]) -> Result<IpAddr, WireError> {
+        if b.len() != 16 {
+            return Err(WireError::InvalidLength(b.len()));
+        }
+
+        let mut octets = [0u8; 16];
+        for (dst, src) in octets.iter_mut().zip(b.iter()) {
+            *dst = *src;
+        }
+        Ok(IpAddr::from_packed(octets))
+    }
+
+    /// Write the octets of this address at the start of `buf`, returning the number of bytes written.
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<uint, WireError> {
+        if buf.len() < 16 {
+            return Err(WireError::BufferTooShort);
+        }
+        for (dst, src) in buf.iter_mut().zip(self.octets().iter()) {
+            *dst = *src;
+        }
+        Ok(16)
+    }
+}
+
+///! Convert operations from/to IPv6 addresses embedding an IPv4 address.
+impl IpAddr {
+    /// Get the IPv4 address embedded in an IPv4-mapped address (`::ffff:a.b.c.d`).
+    pub fn to_ipv4_mapped(&self) -> Option<ipv4::IpAddr> {
+        match *self {
+            IpAddr(0, 0, 0, 0, 0, 0xffff, _, _) => Some(self.low_ipv4()),
+            _ => None,
+        }
+    }
+
+    /// Get the IPv4 address embedded in a deprecated IPv4-compatible address (`::a.b.c.d`).
+    ///
+    /// The unspecified (`::`) and loopback (`::1`) addresses aren't considered IPv4-compatible.
+    pub fn to_ipv4_compatible(&self) -> Option<ipv4::IpAddr> {
+        match *self {
+            IpAddr(0, 0, 0, 0, 0, 0, 0, 0) | IpAddr(0, 0, 0, 0, 0, 0, 0, 1) => None,
+            IpAddr(0, 0, 0, 0, 0, 0, _, _) => Some(self.low_ipv4()),
+            _ => None,
+        }
+    }
+
+    /// Get the IPv4 address of the 6to4 router embedded in a 6to4 address (`2002::/16`, RFC 3056).
+    pub fn sixtofour(&self) -> Option<ipv4::IpAddr> {
+        match *self {
+            IpAddr(0x2002, b, c, _, _, _, _, _) => Some(ipv4_from_words(b, c)),
+            _ => None,
+        }
+    }
+
+    /// Get the server IPv4 address, and the client IPv4 address and UDP port, embedded in a
+    /// Teredo address (`2001::/32`, RFC 4380).
+    ///
+    /// The client address and port are stored obfuscated (bit-inverted), and are returned in clear.
+    pub fn teredo(&self) -> Option<(ipv4::IpAddr, ipv4::IpAddr, u16)> {
+        match *self {
+            IpAddr(0x2001, 0, c, d, _, f, g, h) => {
+                Some((ipv4_from_words(c, d), ipv4_from_words(!g, !h), !f))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the IPv4 address embedded in an ISATAP interface identifier (`::0:5efe:a.b.c.d` or
+    /// `::200:5efe:a.b.c.d` when the IPv4 address is globally unique, RFC 5214).
+    pub fn isatap(&self) -> Option<ipv4::IpAddr> {
+        match *self {
+            IpAddr(_, _, _, _, 0, 0x5efe, _, _) | IpAddr(_, _, _, _, 0x200, 0x5efe, _, _) => Some(self.low_ipv4()),
+            _ => None,
+        }
+    }
+
+    /// The IPv4 address made of the 32 lowest bits of this address.
+    fn low_ipv4(&self) -> ipv4::IpAddr {
+        let &IpAddr(_, _, _, _, _, _, g, h) = self;
+        ipv4_from_words(g, h)
+    }
+}
+
+fn ipv4_from_words(hi: u16, lo: u16) -> ipv4::IpAddr {
+    ipv4::IpAddr((hi >> 8) as u8, hi as u8, (lo >> 8) as u8, lo as u8)
+}
+
+///! Interface identifiers derived from MAC addresses (RFC 4291, appendix A).
+impl IpAddr {
+    /// Get the interface identifier of this address: its 64 lowest bits.
+    pub fn interface_identifier(&self) -> Eui64 {
+        Eui64::from_u64(self.to_u128()[1])
+    }
+
+    /// `true` if the interface identifier of this address is reserved and mustn't be used for
+    /// unicast addresses (RFC 5453): the Subnet-Router anycast identifier, the identifiers
+    /// derived from the IANA Ethernet block, and the subnet anycast identifiers.
+    pub fn has_reserved_interface_identifier(&self) -> bool {
+        let iid = self.to_u128()[1];
+        iid == 0
+        || (0x0200_5eff_fe00_0000 <= iid && iid <= 0x0200_5eff_feff_ffff)
+        || (0xfdff_ffff_ffff_ff80 <= iid && iid <= 0xfdff_ffff_ffff_ffff)
+    }
+
+    /// Create the `fe80::/64` link-local address of an interface, from its MAC address.
+    pub fn link_local(mac: Eui48) -> IpAddr {
+        IpAddr::from_u128([0xfe80 << 48, mac.to_modified_eui64().to_u64()])
+    }
+
+    /// Get the MAC address this address interface identifier has been derived from, if it is
+    /// a modified EUI-64 identifier (with `ff:fe` in its middle octets).
+    pub fn to_eui48(&self) -> Option<Eui48> {
+        self.interface_identifier().to_modified_eui64().to_eui48()
+    }
+}
+
+/// The scope of a multicast address (RFC 7346).
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub enum MulticastScope {
+    InterfaceLocal,
+    LinkLocal,
+    RealmLocal,
+    AdminLocal,
+    SiteLocal,
+    OrganizationLocal,
+    Global,
+    /// The reserved scopes `0` and `f`.
+    Reserved(u8),
+    /// The scopes left unassigned, which administrators may use for other multicast regions.
+    Unassigned(u8),
+}
+
+impl MulticastScope {
+    /// Create a `MulticastScope` instance from its 4-bits value.
+    pub fn from_u8(n: u8) -> MulticastScope {
+        match n & 0xf {
+            0x1 => MulticastScope::InterfaceLocal,
+            0x2 => MulticastScope::LinkLocal,
+            0x3 => MulticastScope::RealmLocal,
+            0x4 => MulticastScope::AdminLocal,
+            0x5 => MulticastScope::SiteLocal,
+            0x8 => MulticastScope::OrganizationLocal,
+            0xe => MulticastScope::Global,
+            n @ 0x0 | n @ 0xf => MulticastScope::Reserved(n),
+            n => MulticastScope::Unassigned(n),
+        }
+    }
+
+    /// Get the 4-bits value of this scope.
+    pub fn to_u8(&self) -> u8 {
+        match *self {
+            MulticastScope::InterfaceLocal => 0x1,
+            MulticastScope::LinkLocal => 0x2,
+            MulticastScope::RealmLocal => 0x3,
+            MulticastScope::AdminLocal => 0x4,
+            MulticastScope::SiteLocal => 0x5,
+            MulticastScope::OrganizationLocal => 0x8,
+            MulticastScope::Global => 0xe,
+            MulticastScope::Reserved(n) | MulticastScope::Unassigned(n) => n & 0xf,
+        }
+    }
+}
+
+/// The flags of a multicast address (RFC 4291, RFC 3306 and RFC 3956).
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub struct MulticastFlags {
+    /// R flag: the address embeds the address of its rendezvous point.
+    pub rendezvous_point: bool,
+    /// P flag: the address is based on a unicast network prefix.
+    pub prefix: bool,
+    /// T flag: the address is transient (not permanently assigned by the IANA).
+    pub transient: bool,
+}
+
+///! Multicast addresses (RFC 4291, section 2.7).
+impl IpAddr {
+    /// `true` if this address is a multicast address (`ff00::/8`).
+    pub fn is_multicast(&self) -> bool {
+        let &IpAddr(a, _, _, _, _, _, _, _) = self;
+        a >> 8 == 0xff
+    }
+
+    /// Create the solicited-node multicast address (`ff02::1:ff00:0/104`) of this address,
+    /// used by Neighbor Discovery to resolve it.
+    pub fn solicited_node_multicast(&self) -> IpAddr {
+        let &IpAddr(_, _, _, _, _, _, g, h) = self;
+        IpAddr(0xff02, 0, 0, 0, 0, 1, 0xff00 | (g & 0xff), h)
+    }
+
+    /// Get the scope of this multicast address.
+    pub fn multicast_scope(&self) -> Option<MulticastScope> {
+        let &IpAddr(a, _, _, _, _, _, _, _) = self;
+        if self.is_multicast() {
+            Some(MulticastScope::from_u8(a as u8))
+        } else {
+            None
+        }
+    }
+
+    /// Get the flags of this multicast address.
+    pub fn multicast_flags(&self) -> Option<MulticastFlags> {
+        let &IpAddr(a, _, _, _, _, _, _, _) = self;
+        if self.is_multicast() {
+            Some(MulticastFlags {
+                rendezvous_point: a & 0x40 != 0,
+                prefix: a & 0x20 != 0,
+                transient: a & 0x10 != 0,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Get the address of the rendezvous point embedded in this embedded-RP multicast address
+    /// (`ff70::/12`, RFC 3956): the embedded prefix, with the RP interface ID as last bits.
+    pub fn rendezvous_point(&self) -> Option<IpAddr> {
+        let [hi, lo] = self.to_u128();
+        let header = hi >> 32;
+        let riid = (header >> 8) & 0xf;
+        let plen = (header & 0xff) as uint;
+        match self.multicast_flags() {
+            Some(MulticastFlags { rendezvous_point: true, prefix: true, transient: true })
+                if plen > 0 && plen <= 64 && (header >> 12) & 0xf == 0 => {
+                let prefix = ((hi << 32) | (lo >> 32)) & (!0 << (64 - plen));
+                Some(IpAddr::from_u128([prefix, riid]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the Ethernet multicast address this multicast address is mapped to (`33:33` followed
+    /// by the 32 lowest bits of the address, RFC 2464).
+    pub fn multicast_mac(&self) -> Option<Eui48> {
+        if self.is_multicast() {
+            Some(Eui48::from_u64(0x3333_0000_0000 | (self.to_u128()[1] & 0xffff_ffff)))
+        } else {
+            None
+        }
+    }
+}
+
 ///! Convert operations from/to Rust's standard library IP addresses.
 impl IpAddr {
     /// Create an `ipv6::IpAddr` instance from a Rust's standard library `IpAddr` instance.
@@ -231,6 +550,7 @@ impl FromStr for IpAddr {
 #[cfg(test)]
 mod test {
     use super::IpAddr;
+    use addr::IpAddress;
 
     #[test]
     fn test_num() {
@@ -244,8 +564,19 @@ mod test {
 
         assert_eq!(b - 1, a);
         assert_eq!(d - 1, c);
+        assert_eq!(IpAddr(0, 0, 0, 1, 0, 0, 0, 0x10) - 2, IpAddr(0, 0, 0, 1, 0, 0, 0, 0xe));
 
         assert!(a < b);
+
+        assert_eq!(c.checked_add(1), Some(d));
+        assert_eq!(d.checked_sub(1), Some(c));
+        assert_eq!(IpAddr(0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff).checked_add(1), None);
+        assert_eq!(IpAddr(0, 0, 0, 0, 0, 0, 0, 1).checked_sub(2), None);
+
+        let e = IpAddr(0, 0, 0, 1, 0xffff, 0xffff, 0xffff, 0xfff0);
+        let f = IpAddr(0, 0, 0, 2, 0, 0, 0, 0x10);
+        assert_eq!(e.checked_add(0x20), Some(f));
+        assert_eq!(f.checked_sub(0x20), Some(e));
     }
 
     #[test]
@@ -258,10 +589,153 @@ mod test {
         assert_eq!(!mask, IpAddr(0, 0, 0, 0, 0, 0xffff, 0xffff, 0xffff));
     }
 
+    #[test]
+    fn test_packed() {
+        let ip = IpAddr(0x2001, 0xdb8, 0, 0, 0, 0xff00, 0x42, 0x8329);
+        assert_eq!(IpAddr::from_packed(ip.packed()), ip);
+    }
+
+    #[test]
+    fn test_ipv4_embedding() {
+        use addr::ipv4;
+
+        let ip: IpAddr = "::ffff:192.0.2.33".parse().unwrap();
+        assert_eq!(ip.to_ipv4_mapped(), Some(ipv4::IpAddr(192, 0, 2, 33)));
+        assert_eq!(ip.to_ipv4_compatible(), None);
+
+        let ip: IpAddr = "::192.0.2.33".parse().unwrap();
+        assert_eq!(ip.to_ipv4_mapped(), None);
+        assert_eq!(ip.to_ipv4_compatible(), Some(ipv4::IpAddr(192, 0, 2, 33)));
+
+        assert_eq!(IpAddr(0, 0, 0, 0, 0, 0, 0, 1).to_ipv4_compatible(), None);
+        assert_eq!(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0xffff, 0, 1).to_ipv4_mapped(), None);
+    }
+
+    #[test]
+    fn test_transition() {
+        use addr::ipv4;
+
+        let ip: IpAddr = "2002:c000:204::1".parse().unwrap();
+        assert_eq!(ip.sixtofour(), Some(ipv4::IpAddr(192, 0, 2, 4)));
+        assert_eq!(ip.teredo(), None);
+
+        let ip: IpAddr = "2001:0:4136:e378:8000:63bf:3fff:fdd2".parse().unwrap();
+        assert_eq!(ip.teredo(),
+                   Some((ipv4::IpAddr(65, 54, 227, 120), ipv4::IpAddr(192, 0, 2, 45), 40000)));
+        assert_eq!(ip.sixtofour(), None);
+
+        let ip: IpAddr = "fe80::5efe:192.0.2.143".parse().unwrap();
+        assert_eq!(ip.isatap(), Some(ipv4::IpAddr(192, 0, 2, 143)));
+        let ip: IpAddr = "2001:db8::200:5efe:192.0.2.143".parse().unwrap();
+        assert_eq!(ip.isatap(), Some(ipv4::IpAddr(192, 0, 2, 143)));
+        let ip: IpAddr = "2001:db8::300:5efe:192.0.2.143".parse().unwrap();
+        assert_eq!(ip.isatap(), None);
+    }
+
+    #[test]
+    fn test_link_local() {
+        use eui::Eui48;
+
+        let mac = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd);
+        let ip: IpAddr = "fe80::21b:77ff:fe49:54fd".parse().unwrap();
+
+        assert_eq!(IpAddr::link_local(mac), ip);
+        assert_eq!(ip.to_eui48(), Some(mac));
+        assert_eq!("fe80::21b:77ff:fe49:1".parse::<IpAddr>().unwrap().to_eui48(), None);
+    }
+
+    #[test]
+    fn test_reserved_interface_identifier() {
+        let reserved = ["2001:db8::", "2001:db8::200:5eff:fe00:5213", "2001:db8::fdff:ffff:ffff:ff80",
+                        "2001:db8::fdff:ffff:ffff:ffff"];
+        for ip in reserved.iter() {
+            assert!(ip.parse::<IpAddr>().unwrap().has_reserved_interface_identifier());
+        }
+
+        let allowed = ["2001:db8::1", "2001:db8::200:5eff:ff00:0", "2001:db8::fdff:ffff:ffff:ff7f",
+                       "2001:db8::fe00:0:0:1", "2001:db8::ffff:ffff:ffff:ffff"];
+        for ip in allowed.iter() {
+            assert!(!ip.parse::<IpAddr>().unwrap().has_reserved_interface_identifier());
+        }
+    }
+
+    #[test]
+    fn test_multicast() {
+        use addr::ipv6::{MulticastFlags, MulticastScope};
+        use eui::Eui48;
+
+        let ip: IpAddr = "fe80::2aa:ff:fe28:9c5a".parse().unwrap();
+        assert!(!ip.is_multicast());
+        assert_eq!(ip.solicited_node_multicast(), "ff02::1:ff28:9c5a".parse().unwrap());
+        assert_eq!(ip.multicast_scope(), None);
+        assert_eq!(ip.multicast_flags(), None);
+        assert_eq!(ip.multicast_mac(), None);
+
+        let ip: IpAddr = "ff02::1".parse().unwrap();
+        assert_eq!(ip.multicast_scope(), Some(MulticastScope::LinkLocal));
+        assert_eq!(ip.multicast_flags(),
+                   Some(MulticastFlags { rendezvous_point: false, prefix: false, transient: false }));
+        assert_eq!(ip.multicast_mac(), Some(Eui48(0x33, 0x33, 0, 0, 0, 1)));
+
+        let ip: IpAddr = "ff3e:30:2001:db8::1234".parse().unwrap();
+        assert_eq!(ip.multicast_scope(), Some(MulticastScope::Global));
+        assert_eq!(ip.multicast_flags(),
+                   Some(MulticastFlags { rendezvous_point: false, prefix: true, transient: true }));
+        assert_eq!(ip.rendezvous_point(), None);
+
+        assert_eq!("ff00::1".parse::<IpAddr>().unwrap().multicast_scope(), Some(MulticastScope::Reserved(0)));
+        assert_eq!("ff16::1".parse::<IpAddr>().unwrap().multicast_scope(), Some(MulticastScope::Unassigned(6)));
+        for n in range(0u8, 16) {
+            assert_eq!(MulticastScope::from_u8(n).to_u8(), n);
+        }
+
+        let ip: IpAddr = "ff7e:740:2001:db8:beef:feed::1234".parse().unwrap();
+        assert_eq!(ip.rendezvous_point(), Some("2001:db8:beef:feed::7".parse().unwrap()));
+        let ip: IpAddr = "ff7e:720:2001:db8:beef:feed::1234".parse().unwrap();
+        assert_eq!(ip.rendezvous_point(), Some("2001:db8::7".parse().unwrap()));
+        let ip: IpAddr = "ff7e:741:2001:db8:beef:feed::1234".parse().unwrap();
+        assert_eq!(ip.rendezvous_point(), None);
+    }
+
+    #[test]
+    fn test_bytes() {
+        use wire::WireError;
+
+        let ip = IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let octets = [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        assert_eq!(ip.octets(), octets);
+        assert_eq!(IpAddr::from_bytes(&octets), Ok(ip));
+        assert_eq!(IpAddr::from_bytes(&octets[..15]), Err(WireError::InvalidLength(15)));
+
+        let mut buf = [0xffu8; 17];
+        assert_eq!(ip.write_to(&mut buf), Ok(16));
+        assert_eq!(&buf[..16], octets.as_slice());
+        assert_eq!(buf[16], 0xff);
+        assert_eq!(ip.write_to(&mut buf[..15]), Err(WireError::BufferTooShort));
+    }
+
     #[test]
     fn test_prefixlen() {
         assert_eq!(IpAddr::with_prefixlen(32), IpAddr(0xffff, 0xffff, 0, 0, 0, 0, 0, 0));
         assert_eq!(IpAddr::with_prefixlen(64), IpAddr(0xffff, 0xffff, 0xffff, 0xffff, 0, 0, 0, 0));
         assert_eq!(IpAddr::with_prefixlen(96), IpAddr(0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0, 0));
+        assert_eq!(IpAddr::with_prefixlen(0), IpAddr(0, 0, 0, 0, 0, 0, 0, 0));
+        assert_eq!(IpAddr::with_prefixlen(128), IpAddr(0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff));
+    }
+
+    #[test]
+    fn test_masks() {
+        let mask = IpAddr(0xffff, 0xffff, 0xffff, 0xfff0, 0, 0, 0, 0);
+        assert_eq!(mask.prefix_len_from_mask(), Some(60));
+        assert_eq!(IpAddr::with_prefixlen(100).prefix_len_from_mask(), Some(100));
+        assert_eq!(IpAddr::with_prefixlen(128).prefix_len_from_mask(), Some(128));
+        assert_eq!(IpAddr(0xffff, 0, 0, 0, 0, 0, 0, 1).prefix_len_from_mask(), None);
+
+        let hostmask = IpAddr(0, 0, 0, 0xf, 0xffff, 0xffff, 0xffff, 0xffff);
+        assert!(mask.is_netmask() && !mask.is_hostmask());
+        assert!(hostmask.is_hostmask() && !hostmask.is_netmask());
+        assert_eq!(mask.to_hostmask(), Some(hostmask));
+        assert_eq!(hostmask.to_netmask(), Some(mask));
+        assert_eq!(hostmask.to_hostmask(), None);
     }
 }
@@ -3,6 +3,7 @@ use std::fmt;
 use std::io::IpAddr as StdIpAddr;
 use std::ops::*;
 use std::str::FromStr;
+use wire::WireError;
 pub use self::IpAddr::*;
 pub use self::IpAddrVersion::*;
 
@@ -25,6 +26,35 @@ pub enum IpAddrVersion {
     Ipv6,
 }
 
+/// Operations shared by IPv4 and IPv6 addresses, used to write code once for both versions.
+///
+/// 128-bits integers are represented as two `u64` (see `ipv6::IpAddr::to_u128`).
+pub trait IpAddress: Copy + Eq + Ord + fmt::Show + fmt::String
+    + BitAnd<Output=Self> + BitOr<Output=Self> + BitXor<Output=Self> + Not<Output=Self> {
+    /// Get the corresponding IP address version.
+    fn version(&self) -> IpAddrVersion;
+
+    /// The total number of bits in the address representation: `32` for IPv4, `128` for IPv6.
+    fn bit_width() -> uint;
+
+    /// Create an IP mask with the specified prefixlen.
+    ///
+    /// The provided prefixlen must be in the prefixlen-range (`0` <= `n` <= `bit_width()`).
+    fn with_prefixlen(n: uint) -> Self;
+
+    /// Create an address from a 128-bits integer, keeping only its `bit_width()` lowest bits.
+    fn from_u128(n: [u64; 2]) -> Self;
+
+    /// Convert an address to a 128-bits integer.
+    fn to_u128(&self) -> [u64; 2];
+
+    /// Add `n` to this address, or return `None` if the result overflows the address space.
+    fn checked_add(&self, n: u64) -> Option<Self>;
+
+    /// Subtract `n` from this address, or return `None` if the result underflows the address space.
+    fn checked_sub(&self, n: u64) -> Option<Self>;
+}
+
 macro_rules! mirror(
     ($addr:expr, $ip:ident => $value:expr) => ({
         match $addr {
@@ -69,6 +99,83 @@ impl IpAddr {
     }
 }
 
+///! Netmasks and host (wildcard) masks.
+impl IpAddr {
+    /// Get the prefix length of this netmask, or `None` if its bits aren't contiguous
+    /// (the inverse of `with_prefixlen`).
+    pub fn prefix_len_from_mask(&self) -> Option<uint> {
+        mirror!(*self, ip => ip.prefix_len_from_mask())
+    }
+
+    /// `true` if this address is a netmask: ones followed by zeros.
+    pub fn is_netmask(&self) -> bool {
+        mirror!(*self, ip => ip.is_netmask())
+    }
+
+    /// `true` if this address is a host mask: zeros followed by ones.
+    pub fn is_hostmask(&self) -> bool {
+        mirror!(*self, ip => ip.is_hostmask())
+    }
+
+    /// Convert this netmask to the corresponding host mask, or `None` if it isn't a netmask.
+    pub fn to_hostmask(&self) -> Option<IpAddr> {
+        match *self {
+            Ipv4Addr(ip) => ip.to_hostmask().map(Ipv4Addr),
+            Ipv6Addr(ip) => ip.to_hostmask().map(Ipv6Addr),
+        }
+    }
+
+    /// Convert this host mask to the corresponding netmask, or `None` if it isn't a host mask.
+    pub fn to_netmask(&self) -> Option<IpAddr> {
+        match *self {
+            Ipv4Addr(ip) => ip.to_netmask().map(Ipv4Addr),
+            Ipv6Addr(ip) => ip.to_netmask().map(Ipv6Addr),
+        }
+    }
+}
+
+/// The octets of an `IpAddr`, most significant first, dereferencing to a slice of 4 or 16 bytes.
+#[derive(Copy, Clone)]
+pub struct Octets {
+    bytes: [u8; 16],
+    len: uint,
+}
+
+impl Deref for Octets {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+///! Binary encoding, in network byte order.
+impl IpAddr {
+    /// Get the octets of this address, without allocating.
+    pub fn octets(&self) -> Octets {
+        let mut bytes = [0u8; 16];
+        let len = self.write_to(&mut bytes).unwrap();
+        Octets {
+            bytes: bytes,
+            len: len,
+        }
+    }
+
+    /// Create an `IpAddr` instance from 4 or 16 octets, most significant first.
+    pub fn from_bytes(b: &[u8]) -> Result<IpAddr, WireError> {
+        match b.len() {
+            4 => ipv4::IpAddr::from_bytes(b).map(Ipv4Addr),
+            16 => ipv6::IpAddr::from_bytes(b).map(Ipv6Addr),
+            n => Err(WireError::InvalidLength(n)),
+        }
+    }
+
+    /// Write the octets of this address at the start of `buf`, returning the number of bytes written.
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<uint, WireError> {
+        mirror!(*self, ip => ip.write_to(buf))
+    }
+}
+
 impl Add<uint> for IpAddr {
     type Output = Self;
 
@@ -204,6 +311,22 @@ mod test {
                                      0x00, 0x00, 0xff, 0x00, 0x00, 0x42, 0x83, 0x29]);
     }
 
+    #[test]
+    fn test_bytes() {
+        use wire::WireError;
+
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        assert_eq!(&*ip.octets(), [127, 0, 0, 1].as_slice());
+        assert_eq!(IpAddr::from_bytes(&*ip.octets()), Ok(ip));
+
+        let ip: IpAddr = "2001:db8:0:0:0:ff00:42:8329".parse().unwrap();
+        assert_eq!(ip.octets().len(), 16);
+        assert_eq!(IpAddr::from_bytes(&*ip.octets()), Ok(ip));
+
+        assert_eq!(IpAddr::from_bytes(&[127, 0, 0]), Err(WireError::InvalidLength(3)));
+        assert_eq!(IpAddr::from_bytes(&[]), Err(WireError::InvalidLength(0)));
+    }
+
     #[test]
     fn test_convert() {
         let ip: IpAddr = "127.0.0.1".parse().unwrap();
@@ -236,4 +359,16 @@ mod test {
     fn test_prefixlen() {
         assert_eq!(IpAddr::with_prefixlen(Ipv4, 16), Some(Ipv4Addr(ipv4::IpAddr(255, 255, 0, 0))));
     }
+
+    #[test]
+    fn test_masks() {
+        let mask = Ipv4Addr(ipv4::IpAddr(255, 255, 0, 0));
+        assert_eq!(mask.prefix_len_from_mask(), Some(16));
+        assert_eq!(mask.to_hostmask(), Some(Ipv4Addr(ipv4::IpAddr(0, 0, 255, 255))));
+        assert!(!mask.is_hostmask());
+
+        let mask = Ipv6Addr(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0xffff, 0xffff));
+        assert_eq!(mask.prefix_len_from_mask(), None);
+        assert_eq!(mask.to_netmask(), IpAddr::with_prefixlen(Ipv6, 96));
+    }
 }
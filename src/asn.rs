@@ -0,0 +1,299 @@
+//! Autonomous System Numbers, and tables mapping networks to the AS originating them.
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::{File, IoError, IoResult, InvalidInput};
+use std::str::FromStr;
+use addr::{self, IpAddress};
+use net::{IpNetwork, Ipv4Network, Ipv6Network, Network};
+
+/// An Autonomous System Number, 16 or 32 bits long (RFC 6793).
+#[derive(Copy, Clone, Show, PartialEq, Eq, PartialOrd, Ord, Hash, RustcEncodable, RustcDecodable)]
+pub struct Asn(pub u32);
+
+/// The ASN standing for 32-bits ASNs in the messages of BGP speakers limited to 16 bits.
+pub const AS_TRANS: Asn = Asn(23456);
+
+impl Asn {
+    pub fn to_u32(&self) -> u32 {
+        let &Asn(n) = self;
+        n
+    }
+
+    /// `true` if this ASN fits in 16 bits, and can be used with any BGP speaker.
+    pub fn is_16bit(&self) -> bool {
+        self.to_u32() <= 0xffff
+    }
+
+    /// `true` if this ASN needs 32 bits, and is replaced by `AS_TRANS` for older BGP speakers.
+    pub fn is_32bit(&self) -> bool {
+        !self.is_16bit()
+    }
+
+    /// `true` for the ASNs reserved for private use (RFC 6996): `64512-65534` and
+    /// `4200000000-4294967294`.
+    pub fn is_private(&self) -> bool {
+        match self.to_u32() {
+            64512...65534 | 4200000000...4294967294 => true,
+            _ => false,
+        }
+    }
+
+    /// `true` for the ASNs reserved for documentation (RFC 5398): `64496-64511` and
+    /// `65536-65551`.
+    pub fn is_documentation(&self) -> bool {
+        match self.to_u32() {
+            64496...64511 | 65536...65551 => true,
+            _ => false,
+        }
+    }
+
+    /// `true` for the ASNs which mustn't be used in routing: `0` (RFC 7607), `AS_TRANS`,
+    /// `65535` and `4294967295` (RFC 7300), and `65552-131071` (reserved by the IANA).
+    pub fn is_reserved(&self) -> bool {
+        match self.to_u32() {
+            0 | 23456 | 65535 | 65552...131071 | 4294967295 => true,
+            _ => false,
+        }
+    }
+
+    /// Format this ASN in asdot notation (RFC 5396): `<high>.<low>` for 32-bits ASNs, and plain
+    /// for 16-bits ones.
+    pub fn to_asdot(&self) -> String {
+        let n = self.to_u32();
+        if self.is_16bit() {
+            format!("{}", n)
+        } else {
+            format!("{}.{}", n >> 16, n & 0xffff)
+        }
+    }
+}
+
+impl fmt::String for Asn {
+    /// > ASNs are displayed in asplain notation, with the `AS` prefix (e.g. `AS65000`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AS{}", self.to_u32())
+    }
+}
+
+impl FromStr for Asn {
+    /// > ASNs are accepted in asplain (`65000`) and asdot (`1.10`) notations, optionally with the
+    /// > `AS` prefix.
+    fn from_str(s: &str) -> Option<Asn> {
+        let s = if s.starts_with("AS") || s.starts_with("as") { s.slice_from(2) } else { s };
+        match s.find('.') {
+            Some(i) => match (number(s.slice_to(i), 0xffff), number(s.slice_from(i + 1), 0xffff)) {
+                (Some(high), Some(low)) => Some(Asn(((high << 16) | low) as u32)),
+                _ => None,
+            },
+            None => number(s, 0xffffffff).map(|n| Asn(n as u32)),
+        }
+    }
+}
+
+/// Parse a decimal number, made of digits only and at most `max`.
+fn number(s: &str, max: u64) -> Option<u64> {
+    if s.is_empty() || s.len() > 10 || !s.chars().all(|c| c.is_digit(10)) {
+        return None;
+    }
+
+    match s.parse::<u64>() {
+        Some(n) if n <= max => Some(n),
+        _ => None,
+    }
+}
+
+/// A table of the ASNs originating networks, queried by longest prefix match.
+///
+/// A network may be originated by several ASNs (MOAS), which are kept in insertion order.
+#[derive(Clone, Show)]
+pub struct AsnTable {
+    entries: HashMap<IpNetwork, Vec<Asn>>,
+    /// The prefix lengths of the IPv4 and IPv6 networks of the table.
+    ipv4_prefixes: BTreeSet<uint>,
+    ipv6_prefixes: BTreeSet<uint>,
+}
+
+impl AsnTable {
+    /// Create an empty table.
+    pub fn new() -> AsnTable {
+        AsnTable {
+            entries: HashMap::new(),
+            ipv4_prefixes: BTreeSet::new(),
+            ipv6_prefixes: BTreeSet::new(),
+        }
+    }
+
+    /// Create a table from lines in CAIDA pfx2as (`<address>\t<prefix length>\t<ASNs>`) or
+    /// `<network>\t<ASNs>` form.
+    ///
+    /// Multiple origins are separated by `_`, and the members of AS sets by `,`: they are all
+    /// recorded as origins. Blank lines and `#` comments are skipped; `None` is returned if
+    /// another line can't be parsed.
+    pub fn parse(data: &str) -> Option<AsnTable> {
+        let mut table = AsnTable::new();
+        for line in data.lines_any() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("#") {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(|c: char| c.is_whitespace())
+                                        .filter(|field| !field.is_empty())
+                                        .collect();
+            let (net, origins) = match fields.len() {
+                2 => (fields[0].parse::<IpNetwork>(), fields[1]),
+                3 => (format!("{}/{}", fields[0], fields[1]).parse::<IpNetwork>(), fields[2]),
+                _ => return None,
+            };
+            let net = match net {
+                Some(net) => net,
+                None => return None,
+            };
+
+            for origin in origins.split(|c: char| c == '_' || c == ',') {
+                match origin.parse() {
+                    Some(asn) => table.insert(net, asn),
+                    None => return None,
+                }
+            }
+        }
+        Some(table)
+    }
+
+    /// Create a table from a local file (see `parse`).
+    pub fn load(path: &Path) -> IoResult<AsnTable> {
+        let data = try!(File::open(path).read_to_string());
+        match AsnTable::parse(data.as_slice()) {
+            Some(table) => Ok(table),
+            None => Err(IoError {
+                kind: InvalidInput,
+                desc: "malformed prefix to AS table",
+                detail: None,
+            }),
+        }
+    }
+
+    /// Add an origin ASN of a network. Host bits of the network are ignored.
+    pub fn insert(&mut self, net: IpNetwork, asn: Asn) {
+        let net = match net {
+            Ipv4Network(net) => {
+                self.ipv4_prefixes.insert(net.prefix());
+                Ipv4Network(Network(net.address(), net.prefix()))
+            }
+            Ipv6Network(net) => {
+                self.ipv6_prefixes.insert(net.prefix());
+                Ipv6Network(Network(net.address(), net.prefix()))
+            }
+        };
+
+        if !self.entries.contains_key(&net) {
+            self.entries.insert(net, Vec::new());
+        }
+        let origins = self.entries.get_mut(&net).unwrap();
+        if !origins.contains(&asn) {
+            origins.push(asn);
+        }
+    }
+
+    /// Get the number of networks.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    /// Get the most specific network containing `ip`, with its origin ASNs.
+    pub fn lookup(&self, ip: addr::IpAddr) -> Option<(IpNetwork, &[Asn])> {
+        match ip {
+            addr::Ipv4Addr(ip) => self.longest_match(ip, &self.ipv4_prefixes, Ipv4Network),
+            addr::Ipv6Addr(ip) => self.longest_match(ip, &self.ipv6_prefixes, Ipv6Network),
+        }
+    }
+
+    fn longest_match<A, F>(&self, ip: A, prefixes: &BTreeSet<uint>, wrap: F)
+                           -> Option<(IpNetwork, &[Asn])>
+                           where A: IpAddress, F: Fn(Network<A>) -> IpNetwork {
+        for &prefix in prefixes.iter().rev() {
+            let net = wrap(Network(Network(ip, prefix).address(), prefix));
+            match self.entries.get(&net) {
+                Some(origins) => return Some((net, origins.as_slice())),
+                None => {}
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use addr::{self, ipv4, ipv6};
+    use net::{Ipv4Network, Ipv6Network, Network};
+    use super::{Asn, AsnTable, AS_TRANS};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("AS65000".parse(), Some(Asn(65000)));
+        assert_eq!("as65000".parse(), Some(Asn(65000)));
+        assert_eq!("65000".parse(), Some(Asn(65000)));
+        assert_eq!("1.10".parse(), Some(Asn(65546)));
+        assert_eq!("AS0.65535".parse(), Some(Asn(65535)));
+        assert_eq!("4294967295".parse(), Some(Asn(4294967295)));
+
+        for s in ["", "AS", "4294967296", "65536.0", "1.65536", "1.", ".1", "1.2.3", "-1", "+1",
+                  "AS 1", "0x10"].iter() {
+            assert_eq!(s.parse::<Asn>(), None);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Asn(65000).to_string().as_slice(), "AS65000");
+        assert_eq!(Asn(65000).to_asdot().as_slice(), "65000");
+        assert_eq!(Asn(65546).to_asdot().as_slice(), "1.10");
+        assert_eq!(Asn(4294967295).to_asdot().as_slice(), "65535.65535");
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert!(Asn(65535).is_16bit() && Asn(65536).is_32bit());
+        assert!(Asn(64512).is_private() && Asn(4200000000).is_private());
+        assert!(!Asn(65535).is_private() && !Asn(4294967295).is_private());
+        assert!(Asn(64496).is_documentation() && Asn(65551).is_documentation());
+        assert!(!Asn(65552).is_documentation());
+        assert!(Asn(0).is_reserved() && AS_TRANS.is_reserved() && Asn(100000).is_reserved());
+        assert!(!Asn(13335).is_reserved() && !Asn(13335).is_private());
+    }
+
+    #[test]
+    fn test_table() {
+        let data = "# pfx2as
+192.0.2.0\t24\t64496
+198.51.100.0\t24\t64497_64498
+198.51.100.128\t25\t64499,64500
+2001:db8::/32\tAS64501
+2001:db8:1::/48 64502
+";
+        let table = AsnTable::parse(data).unwrap();
+        assert_eq!(table.len(), 5);
+
+        let lookup = |ip: addr::IpAddr| {
+            table.lookup(ip).map(|(net, origins)| (net, origins.to_vec()))
+        };
+        let ip = addr::Ipv4Addr(ipv4::IpAddr(192, 0, 2, 1));
+        assert_eq!(lookup(ip), Some((Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 0), 24)),
+                                     vec![Asn(64496)])));
+        let ip = addr::Ipv4Addr(ipv4::IpAddr(198, 51, 100, 1));
+        assert_eq!(lookup(ip).unwrap().1, vec![Asn(64497), Asn(64498)]);
+        let ip = addr::Ipv4Addr(ipv4::IpAddr(198, 51, 100, 200));
+        assert_eq!(lookup(ip).unwrap().1, vec![Asn(64499), Asn(64500)]);
+        assert_eq!(lookup(addr::Ipv4Addr(ipv4::IpAddr(203, 0, 113, 1))), None);
+
+        let ip = addr::Ipv6Addr(ipv6::IpAddr(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1));
+        let net = Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 1, 0, 0, 0, 0, 0), 48));
+        assert_eq!(lookup(ip), Some((net, vec![Asn(64502)])));
+        let ip = addr::Ipv6Addr(ipv6::IpAddr(0x2001, 0xdb8, 2, 0, 0, 0, 0, 1));
+        assert_eq!(lookup(ip).unwrap().1, vec![Asn(64501)]);
+
+        assert!(AsnTable::parse("192.0.2.0\t24").is_none());
+        assert!(AsnTable::parse("192.0.2.0/24\tAS").is_none());
+        assert!(AsnTable::parse("192.0.2.0/33\t64496").is_none());
+    }
+}
@@ -0,0 +1,223 @@
+//! Provide operations over EUI-48 (MAC) addresses.
+use std::fmt;
+use std::ops::*;
+use std::str::FromStr;
+use super::{Dialect, Eui64};
+
+const MAX: u64 = (1 << 48) - 1;
+
+#[derive(Copy, Clone, Show, PartialEq, Eq, PartialOrd, Ord, Hash, RustcEncodable, RustcDecodable)]
+pub struct Eui48(pub u8, pub u8, pub u8, pub u8, pub u8, pub u8);
+
+impl Eui48 {
+    /// The binary representation of this address (most significant octet first).
+    pub fn packed(&self) -> [u8; 6] {
+        let &Eui48(a, b, c, d, e, f) = self;
+        [a, b, c, d, e, f]
+    }
+
+    /// Create an `Eui48` instance from its binary representation (most significant octet first).
+    pub fn from_packed(b: [u8; 6]) -> Eui48 {
+        Eui48(b[0], b[1], b[2], b[3], b[4], b[5])
+    }
+
+    /// Create an `Eui48` instance from an integer, keeping only its 48 lowest bits.
+    pub fn from_u64(n: u64) -> Eui48 {
+        let mut b = [0u8; 6];
+        for (i, byte) in b.iter_mut().enumerate() {
+            *byte = (n >> (8 * (5 - i))) as u8;
+        }
+        Eui48::from_packed(b)
+    }
+
+    /// Convert an `Eui48` instance to a 48-bits integer.
+    pub fn to_u64(&self) -> u64 {
+        self.packed().iter().fold(0, |n, &b| (n << 8) | b as u64)
+    }
+
+    /// The 24-bits Organizationally Unique Identifier, made of the first three octets.
+    pub fn oui(&self) -> u32 {
+        let &Eui48(a, b, c, _, _, _) = self;
+        ((a as u32) << 16) | ((b as u32) << 8) | c as u32
+    }
+
+    /// `true` if the I/G bit (least significant bit of the first octet) is cleared.
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// `true` if the I/G bit (least significant bit of the first octet) is set.
+    pub fn is_multicast(&self) -> bool {
+        let &Eui48(a, _, _, _, _, _) = self;
+        a & 0x01 != 0
+    }
+
+    /// `true` if the U/L bit (second least significant bit of the first octet) is cleared,
+    /// meaning the address has been assigned by its manufacturer from an IEEE-registered OUI.
+    pub fn is_universal(&self) -> bool {
+        !self.is_local()
+    }
+
+    /// `true` if the U/L bit (second least significant bit of the first octet) is set,
+    /// meaning the address has been locally administered.
+    pub fn is_local(&self) -> bool {
+        let &Eui48(a, _, _, _, _, _) = self;
+        a & 0x02 != 0
+    }
+
+    /// `true` if all the bits are set.
+    pub fn is_broadcast(&self) -> bool {
+        self.to_u64() == MAX
+    }
+
+    /// Create the EUI-64 address of this address, by inserting `ff:fe` after the OUI.
+    pub fn to_eui64(&self) -> Eui64 {
+        let &Eui48(a, b, c, d, e, f) = self;
+        Eui64(a, b, c, 0xff, 0xfe, d, e, f)
+    }
+
+    /// Create the modified EUI-64 interface identifier of this address, as used by IPv6
+    /// stateless address autoconfiguration (RFC 4291, appendix A).
+    pub fn to_modified_eui64(&self) -> Eui64 {
+        self.to_eui64().to_modified_eui64()
+    }
+
+    /// Format this address with the specified dialect.
+    pub fn format(&self, dialect: Dialect) -> String {
+        super::format(&self.packed(), dialect)
+    }
+}
+
+impl Add<u64> for Eui48 {
+    type Output = Self;
+
+    /// > The addition wraps around the 48-bits address space.
+    fn add(self, rhs: u64) -> Eui48 {
+        Eui48::from_u64(self.to_u64().wrapping_add(rhs))
+    }
+}
+
+impl Sub<u64> for Eui48 {
+    type Output = Self;
+
+    /// > The subtraction wraps around the 48-bits address space.
+    fn sub(self, rhs: u64) -> Eui48 {
+        Eui48::from_u64(self.to_u64().wrapping_sub(rhs))
+    }
+}
+
+impl BitXor<Eui48> for Eui48 {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Eui48) -> Eui48 {
+        Eui48::from_u64(self.to_u64() ^ rhs.to_u64())
+    }
+}
+
+impl BitOr<Eui48> for Eui48 {
+    type Output = Self;
+
+    fn bitor(self, rhs: Eui48) -> Eui48 {
+        Eui48::from_u64(self.to_u64() | rhs.to_u64())
+    }
+}
+
+impl BitAnd<Eui48> for Eui48 {
+    type Output = Self;
+
+    fn bitand(self, rhs: Eui48) -> Eui48 {
+        Eui48::from_u64(self.to_u64() & rhs.to_u64())
+    }
+}
+
+impl Not for Eui48 {
+    type Output = Self;
+
+    fn not(self) -> Eui48 {
+        Eui48::from_u64(!self.to_u64())
+    }
+}
+
+impl fmt::String for Eui48 {
+    /// > Addresses are displayed in the IEEE canonical form (`Dialect::Ieee`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(Dialect::Ieee))
+    }
+}
+
+impl FromStr for Eui48 {
+    /// > Addresses are accepted in any of the `Dialect` forms.
+    fn from_str(s: &str) -> Option<Eui48> {
+        let mut b = [0u8; 6];
+        if super::parse(s, &mut b) {
+            Some(Eui48::from_packed(b))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Eui48;
+    use eui::Dialect;
+
+    #[test]
+    fn test_parse() {
+        let mac = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd);
+
+        assert_eq!("00:1b:77:49:54:fd".parse(), Some(mac));
+        assert_eq!("00-1B-77-49-54-FD".parse(), Some(mac));
+        assert_eq!("001b.7749.54fd".parse(), Some(mac));
+        assert_eq!("001b774954fd".parse(), Some(mac));
+        assert_eq!("00:1b:77:49:54".parse::<Eui48>(), None);
+        assert_eq!("00:1b:77:ff:fe:49:54:fd".parse::<Eui48>(), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let mac = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd);
+
+        assert_eq!(mac.to_string().as_slice(), "00-1B-77-49-54-FD");
+        assert_eq!(mac.format(Dialect::UnixExpanded).as_slice(), "00:1b:77:49:54:fd");
+        assert_eq!(mac.format(Dialect::Cisco).as_slice(), "001b.7749.54fd");
+    }
+
+    #[test]
+    fn test_bits() {
+        let mac = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd);
+        assert!(mac.is_unicast() && mac.is_universal());
+        assert_eq!(mac.oui(), 0x001b77);
+
+        let mac = Eui48(0x01, 0x00, 0x5e, 0x00, 0x00, 0x01);
+        assert!(mac.is_multicast() && mac.is_universal());
+
+        let mac = Eui48(0x02, 0x42, 0xac, 0x11, 0x00, 0x02);
+        assert!(mac.is_unicast() && mac.is_local());
+
+        assert!(Eui48(0xff, 0xff, 0xff, 0xff, 0xff, 0xff).is_broadcast());
+    }
+
+    #[test]
+    fn test_eui64() {
+        use eui::Eui64;
+
+        let mac = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd);
+        assert_eq!(mac.to_eui64(), Eui64(0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd));
+        assert_eq!(mac.to_modified_eui64(), Eui64(0x02, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd));
+        assert_eq!(mac.to_eui64().to_eui48(), Some(mac));
+    }
+
+    #[test]
+    fn test_num() {
+        let mac = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xff);
+
+        assert_eq!(mac + 1, Eui48(0x00, 0x1b, 0x77, 0x49, 0x55, 0x00));
+        assert_eq!(mac - 0xff, Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0x00));
+        assert_eq!(Eui48(0xff, 0xff, 0xff, 0xff, 0xff, 0xff) + 1, Eui48(0, 0, 0, 0, 0, 0));
+        assert_eq!(Eui48(0, 0, 0, 0, 0, 0) - 1, Eui48(0xff, 0xff, 0xff, 0xff, 0xff, 0xff));
+        assert_eq!(mac.to_u64(), 0x001b774954ff);
+        assert_eq!(Eui48::from_u64(0x001b774954ff), mac);
+        assert!(mac < mac + 1);
+    }
+}
@@ -0,0 +1,215 @@
+//! Provide operations over EUI-64 addresses.
+use std::fmt;
+use std::ops::*;
+use std::str::FromStr;
+use super::{Dialect, Eui48};
+
+const MAX: u64 = !0;
+
+#[derive(Copy, Clone, Show, PartialEq, Eq, PartialOrd, Ord, Hash, RustcEncodable, RustcDecodable)]
+pub struct Eui64(pub u8, pub u8, pub u8, pub u8, pub u8, pub u8, pub u8, pub u8);
+
+impl Eui64 {
+    /// The binary representation of this address (most significant octet first).
+    pub fn packed(&self) -> [u8; 8] {
+        let &Eui64(a, b, c, d, e, f, g, h) = self;
+        [a, b, c, d, e, f, g, h]
+    }
+
+    /// Create an `Eui64` instance from its binary representation (most significant octet first).
+    pub fn from_packed(b: [u8; 8]) -> Eui64 {
+        Eui64(b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7])
+    }
+
+    /// Create an `Eui64` instance from a 64-bits integer.
+    pub fn from_u64(n: u64) -> Eui64 {
+        let mut b = [0u8; 8];
+        for (i, byte) in b.iter_mut().enumerate() {
+            *byte = (n >> (8 * (7 - i))) as u8;
+        }
+        Eui64::from_packed(b)
+    }
+
+    /// Convert an `Eui64` instance to a 64-bits integer.
+    pub fn to_u64(&self) -> u64 {
+        self.packed().iter().fold(0, |n, &b| (n << 8) | b as u64)
+    }
+
+    /// The 24-bits Organizationally Unique Identifier, made of the first three octets.
+    pub fn oui(&self) -> u32 {
+        let &Eui64(a, b, c, _, _, _, _, _) = self;
+        ((a as u32) << 16) | ((b as u32) << 8) | c as u32
+    }
+
+    /// `true` if the I/G bit (least significant bit of the first octet) is cleared.
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// `true` if the I/G bit (least significant bit of the first octet) is set.
+    pub fn is_multicast(&self) -> bool {
+        let &Eui64(a, _, _, _, _, _, _, _) = self;
+        a & 0x01 != 0
+    }
+
+    /// `true` if the U/L bit (second least significant bit of the first octet) is cleared,
+    /// meaning the address has been assigned by its manufacturer from an IEEE-registered OUI.
+    pub fn is_universal(&self) -> bool {
+        !self.is_local()
+    }
+
+    /// `true` if the U/L bit (second least significant bit of the first octet) is set,
+    /// meaning the address has been locally administered.
+    pub fn is_local(&self) -> bool {
+        let &Eui64(a, _, _, _, _, _, _, _) = self;
+        a & 0x02 != 0
+    }
+
+    /// `true` if all the bits are set.
+    pub fn is_broadcast(&self) -> bool {
+        self.to_u64() == MAX
+    }
+
+    /// Get the EUI-48 address this address has been created from, if its fourth and fifth
+    /// octets are `ff:fe`.
+    pub fn to_eui48(&self) -> Option<Eui48> {
+        match *self {
+            Eui64(a, b, c, 0xff, 0xfe, f, g, h) => Some(Eui48(a, b, c, f, g, h)),
+            _ => None,
+        }
+    }
+
+    /// Convert between this address and the modified EUI-64 format used by IPv6 interface
+    /// identifiers, by inverting the U/L bit (RFC 4291, appendix A).
+    ///
+    /// The conversion is its own inverse.
+    pub fn to_modified_eui64(&self) -> Eui64 {
+        let &Eui64(a, b, c, d, e, f, g, h) = self;
+        Eui64(a ^ 0x02, b, c, d, e, f, g, h)
+    }
+
+    /// Format this address with the specified dialect.
+    pub fn format(&self, dialect: Dialect) -> String {
+        super::format(&self.packed(), dialect)
+    }
+}
+
+impl Add<u64> for Eui64 {
+    type Output = Self;
+
+    /// > The addition wraps around the 64-bits address space.
+    fn add(self, rhs: u64) -> Eui64 {
+        Eui64::from_u64(self.to_u64().wrapping_add(rhs))
+    }
+}
+
+impl Sub<u64> for Eui64 {
+    type Output = Self;
+
+    /// > The subtraction wraps around the 64-bits address space.
+    fn sub(self, rhs: u64) -> Eui64 {
+        Eui64::from_u64(self.to_u64().wrapping_sub(rhs))
+    }
+}
+
+impl BitXor<Eui64> for Eui64 {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Eui64) -> Eui64 {
+        Eui64::from_u64(self.to_u64() ^ rhs.to_u64())
+    }
+}
+
+impl BitOr<Eui64> for Eui64 {
+    type Output = Self;
+
+    fn bitor(self, rhs: Eui64) -> Eui64 {
+        Eui64::from_u64(self.to_u64() | rhs.to_u64())
+    }
+}
+
+impl BitAnd<Eui64> for Eui64 {
+    type Output = Self;
+
+    fn bitand(self, rhs: Eui64) -> Eui64 {
+        Eui64::from_u64(self.to_u64() & rhs.to_u64())
+    }
+}
+
+impl Not for Eui64 {
+    type Output = Self;
+
+    fn not(self) -> Eui64 {
+        Eui64::from_u64(!self.to_u64())
+    }
+}
+
+impl fmt::String for Eui64 {
+    /// > Addresses are displayed in the IEEE canonical form (`Dialect::Ieee`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(Dialect::Ieee))
+    }
+}
+
+impl FromStr for Eui64 {
+    /// > Addresses are accepted in any of the `Dialect` forms.
+    fn from_str(s: &str) -> Option<Eui64> {
+        let mut b = [0u8; 8];
+        if super::parse(s, &mut b) {
+            Some(Eui64::from_packed(b))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Eui64;
+    use eui::Dialect;
+
+    #[test]
+    fn test_parse() {
+        let eui = Eui64(0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd);
+
+        assert_eq!("00:1b:77:ff:fe:49:54:fd".parse(), Some(eui));
+        assert_eq!("00-1B-77-FF-FE-49-54-FD".parse(), Some(eui));
+        assert_eq!("001b.77ff.fe49.54fd".parse(), Some(eui));
+        assert_eq!("001b77fffe4954fd".parse(), Some(eui));
+        assert_eq!("00:1b:77:49:54:fd".parse::<Eui64>(), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let eui = Eui64(0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd);
+
+        assert_eq!(eui.to_string().as_slice(), "00-1B-77-FF-FE-49-54-FD");
+        assert_eq!(eui.format(Dialect::Unix).as_slice(), "0:1b:77:ff:fe:49:54:fd");
+        assert_eq!(eui.format(Dialect::Bare).as_slice(), "001B77FFFE4954FD");
+    }
+
+    #[test]
+    fn test_eui48() {
+        use eui::Eui48;
+
+        let eui = Eui64(0x02, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd);
+        assert_eq!(eui.to_eui48(), Some(Eui48(0x02, 0x1b, 0x77, 0x49, 0x54, 0xfd)));
+        assert_eq!(eui.to_modified_eui64().to_eui48(), Some(Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd)));
+        assert_eq!(eui.to_modified_eui64().to_modified_eui64(), eui);
+        assert_eq!(Eui64(0x02, 0x1b, 0x77, 0xff, 0xff, 0x49, 0x54, 0xfd).to_eui48(), None);
+    }
+
+    #[test]
+    fn test_num() {
+        let eui = Eui64(0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd);
+
+        assert_eq!(eui + 3, Eui64(0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x55, 0x00));
+        assert_eq!(eui.to_u64(), 0x001b77fffe4954fd);
+        assert_eq!(eui.oui(), 0x001b77);
+        assert!(eui.is_unicast() && eui.is_universal());
+
+        let (zero, last) = (Eui64::from_u64(0), Eui64::from_u64(!0));
+        assert_eq!(zero - 1, last);
+        assert_eq!(last + 1, zero);
+    }
+}
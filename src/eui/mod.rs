@@ -0,0 +1,146 @@
+//! Provide operations over IEEE EUI-48 (MAC) and EUI-64 addresses.
+pub use self::eui48::Eui48;
+pub use self::eui64::Eui64;
+
+pub mod eui48;
+pub mod eui64;
+pub mod oui;
+
+/// The textual representations of EUI addresses, shown with `00:1b:77:49:54:fd` as example.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// IEEE canonical form, hyphen-separated uppercase octets: `00-1B-77-49-54-FD`.
+    Ieee,
+    /// Colon-separated lowercase octets, without leading zeroes: `0:1b:77:49:54:fd`.
+    Unix,
+    /// Colon-separated lowercase octets: `00:1b:77:49:54:fd`.
+    UnixExpanded,
+    /// Dot-separated groups of two lowercase octets: `001b.7749.54fd`.
+    Cisco,
+    /// Uppercase octets, without separators: `001B774954FD`.
+    Bare,
+}
+
+/// Format the octets of an EUI address with the specified dialect.
+fn format(bytes: &[u8], dialect: Dialect) -> String {
+    let parts: Vec<String> = match dialect {
+        Dialect::Ieee => bytes.iter().map(|b| format!("{:02X}", *b)).collect(),
+        Dialect::Unix => bytes.iter().map(|b| format!("{:x}", *b)).collect(),
+        Dialect::UnixExpanded => bytes.iter().map(|b| format!("{:02x}", *b)).collect(),
+        Dialect::Cisco => bytes.chunks(2).map(|b| format!("{:02x}{:02x}", b[0], b[1])).collect(),
+        Dialect::Bare => bytes.iter().map(|b| format!("{:02X}", *b)).collect(),
+    };
+
+    let sep = match dialect {
+        Dialect::Ieee => "-",
+        Dialect::Unix | Dialect::UnixExpanded => ":",
+        Dialect::Cisco => ".",
+        Dialect::Bare => "",
+    };
+
+    parts.connect(sep)
+}
+
+/// Parse an EUI address written in any dialect, filling `bytes` with its octets.
+///
+/// Hexadecimal digits are case-insensitive, and the colon and hyphen separated forms
+/// accept octets without their leading zero.
+fn parse(s: &str, bytes: &mut [u8]) -> bool {
+    let len = bytes.len();
+
+    if s.contains_char(':') || s.contains_char('-') {
+        let sep = if s.contains_char(':') { ':' } else { '-' };
+        let groups: Vec<&str> = s.split(sep).collect();
+        if groups.len() != len {
+            return false;
+        }
+
+        for (b, group) in bytes.iter_mut().zip(groups.iter()) {
+            match parse_hex(*group, 2) {
+                Some(n) => *b = n as u8,
+                None => return false,
+            }
+        }
+    } else if s.contains_char('.') {
+        let groups: Vec<&str> = s.split('.').collect();
+        if groups.len() * 2 != len {
+            return false;
+        }
+
+        for (i, group) in groups.iter().enumerate() {
+            if group.len() != 4 {
+                return false;
+            }
+            match parse_hex(*group, 4) {
+                Some(n) => {
+                    bytes[2 * i] = (n >> 8) as u8;
+                    bytes[2 * i + 1] = n as u8;
+                }
+                None => return false,
+            }
+        }
+    } else {
+        if s.len() != 2 * len || !s.chars().all(|c| c.is_digit(16)) {
+            return false;
+        }
+
+        for (i, b) in bytes.iter_mut().enumerate() {
+            match parse_hex(s.slice(2 * i, 2 * i + 2), 2) {
+                Some(n) => *b = n as u8,
+                None => return false,
+            }
+        }
+    }
+
+    true
+}
+
+/// Parse between 1 and `max` hexadecimal digits.
+fn parse_hex(s: &str, max: uint) -> Option<u16> {
+    if s.len() == 0 || s.len() > max {
+        return None;
+    }
+
+    let mut n = 0u16;
+    for c in s.chars() {
+        match c.to_digit(16) {
+            Some(d) => n = (n << 4) | d as u16,
+            None => return None,
+        }
+    }
+    Some(n)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format, parse, Dialect};
+
+    #[test]
+    fn test_format() {
+        let bytes = [0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd];
+
+        assert_eq!(format(&bytes, Dialect::Ieee).as_slice(), "00-1B-77-49-54-FD");
+        assert_eq!(format(&bytes, Dialect::Unix).as_slice(), "0:1b:77:49:54:fd");
+        assert_eq!(format(&bytes, Dialect::UnixExpanded).as_slice(), "00:1b:77:49:54:fd");
+        assert_eq!(format(&bytes, Dialect::Cisco).as_slice(), "001b.7749.54fd");
+        assert_eq!(format(&bytes, Dialect::Bare).as_slice(), "001B774954FD");
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = [0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd];
+
+        for s in ["00:1b:77:49:54:fd", "0:1b:77:49:54:fd", "00-1B-77-49-54-FD",
+                  "001b.7749.54fd", "001b774954fd", "001B774954FD"].iter() {
+            let mut bytes = [0u8; 6];
+            assert!(parse(*s, &mut bytes));
+            assert_eq!(bytes, expected);
+        }
+
+        for s in ["00:1b:77:49:54", "00:1b:77:49:54:fd:00", "00:1b-77:49:54:fd", "000:1b:77:49:54:fd",
+                  "001b.7749.54f", "001b774954f", "001b774954fg", ""].iter() {
+            let mut bytes = [0u8; 6];
+            assert!(!parse(*s, &mut bytes));
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! IEEE Registration Authority lookups: map an EUI-48 to the organization its prefix has been
+//! assigned to.
+//!
+//! The registry is read from the public listings of the IEEE, either in their text form
+//! (`oui.txt`, `mam.txt`, `oui36.txt`) or in their CSV form (`oui.csv`, `mam.csv`, `oui36.csv`).
+//! The three block sizes (MA-L, MA-M and MA-S) can be merged into a single `Registry`, which then
+//! answers with the most specific assignment.
+use std::collections::HashMap;
+use std::io::{File, IoError, IoResult, InvalidInput};
+use std::mem;
+use super::Eui48;
+
+/// A small snapshot of the MA-L listing, restricted to some widespread vendor and hypervisor
+/// prefixes. It doesn't carry the organization addresses.
+static SNAPSHOT: &'static str = include_str!("oui.csv");
+
+/// The size of an IEEE-assigned block.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub enum Assignment {
+    /// MAC Address Block Large, a 24-bits prefix (formerly the OUI).
+    MaL,
+    /// MAC Address Block Medium, a 28-bits prefix.
+    MaM,
+    /// MAC Address Block Small, a 36-bits prefix (formerly the IAB and OUI-36).
+    MaS,
+}
+
+impl Assignment {
+    /// Get the length of the assigned prefix, in bits.
+    pub fn prefix_len(&self) -> uint {
+        match *self {
+            Assignment::MaL => 24,
+            Assignment::MaM => 28,
+            Assignment::MaS => 36,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Assignment> {
+        match name {
+            "MA-L" => Some(Assignment::MaL),
+            "MA-M" => Some(Assignment::MaM),
+            "MA-S" => Some(Assignment::MaS),
+            _ => None,
+        }
+    }
+}
+
+/// A block of addresses assigned to an organization.
+#[derive(Clone, Show, PartialEq, Eq)]
+pub struct Registration {
+    pub assignment: Assignment,
+    /// The assigned prefix, as the `prefix_len` most significant bits of a 48-bits integer.
+    pub prefix: u64,
+    pub organization: String,
+    /// The postal address of the organization, on a single line.
+    pub address: String,
+}
+
+impl Registration {
+    /// Get the first address of the assigned block.
+    pub fn first(&self) -> Eui48 {
+        Eui48::from_u64(self.prefix << (48 - self.assignment.prefix_len()))
+    }
+
+    /// `true` if this address has been allocated from the assigned block.
+    pub fn contains(&self, eui: Eui48) -> bool {
+        eui.to_u64() >> (48 - self.assignment.prefix_len()) == self.prefix
+    }
+}
+
+/// A set of IEEE registrations, queried by address.
+#[derive(Clone, Show)]
+pub struct Registry {
+    entries: HashMap<(uint, u64), Registration>,
+}
+
+impl Registry {
+    /// Create an empty registry.
+    pub fn new() -> Registry {
+        Registry {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Create a registry from the content of an IEEE listing, in text or CSV form.
+    ///
+    /// `None` is returned if a registration can't be parsed.
+    pub fn parse(data: &str) -> Option<Registry> {
+        let is_csv = data.lines_any().find(|line| !line.trim().is_empty())
+                         .map_or(false, |line| line.starts_with("Registry,"));
+        let registrations = if is_csv { parse_csv(data) } else { parse_txt(data) };
+
+        registrations.map(|registrations| {
+            let mut registry = Registry::new();
+            for registration in registrations.into_iter() {
+                registry.insert(registration);
+            }
+            registry
+        })
+    }
+
+    /// Create a registry from a local IEEE listing, in text or CSV form.
+    pub fn load(path: &Path) -> IoResult<Registry> {
+        let data = try!(File::open(path).read_to_string());
+        match Registry::parse(data.as_slice()) {
+            Some(registry) => Ok(registry),
+            None => Err(IoError {
+                kind: InvalidInput,
+                desc: "malformed IEEE registry listing",
+                detail: None,
+            }),
+        }
+    }
+
+    /// Create a registry from the snapshot embedded in this crate.
+    ///
+    /// The snapshot only covers a few common MA-L assignments: load the listings published by the
+    /// IEEE for complete lookups.
+    pub fn embedded() -> Registry {
+        Registry::parse(SNAPSHOT).unwrap()
+    }
+
+    /// Add a registration, replacing any registration of the same block.
+    pub fn insert(&mut self, registration: Registration) {
+        let key = (registration.assignment.prefix_len(), registration.prefix);
+        self.entries.insert(key, registration);
+    }
+
+    /// Add all registrations of `other`, e.g. to combine the MA-L, MA-M and MA-S listings.
+    pub fn merge(&mut self, other: Registry) {
+        for (_, registration) in other.entries.into_iter() {
+            self.insert(registration);
+        }
+    }
+
+    /// Get the number of registrations.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    /// Get the most specific registration this address has been allocated from.
+    pub fn lookup(&self, eui: Eui48) -> Option<&Registration> {
+        let n = eui.to_u64();
+        for &len in [36u, 28, 24].iter() {
+            match self.entries.get(&(len, n >> (48 - len))) {
+                Some(registration) => return Some(registration),
+                None => {}
+            }
+        }
+        None
+    }
+}
+
+/// Parse the text form of a listing.
+///
+/// Each registration is a paragraph, starting with the OUI in `(hex)` form, followed by the
+/// assigned block in `(base 16)` form (a range of the low 24 bits for MA-M and MA-S), then the
+/// lines of the organization address.
+fn parse_txt(data: &str) -> Option<Vec<Registration>> {
+    let mut registrations = Vec::new();
+    let mut current: Option<Registration> = None;
+    let mut oui = None;
+
+    for line in data.lines_any() {
+        if line.trim().is_empty() {
+            registrations.extend(current.take().into_iter());
+            oui = None;
+        } else if line.contains("(hex)") {
+            let (key, _) = split_marker(line, "(hex)");
+            let key: String = key.chars().filter(|&c| c != '-').collect();
+            match parse_hex(key.as_slice(), 6) {
+                Some(n) => oui = Some(n),
+                None => return None,
+            }
+        } else if line.contains("(base 16)") {
+            let (key, organization) = split_marker(line, "(base 16)");
+            let bounds: Vec<Option<u64>> = key.split('-').map(|n| parse_hex(n, 6)).collect();
+            let (assignment, prefix) = match (oui, bounds.len(), bounds[0], *bounds.last().unwrap()) {
+                (_, 1, Some(n), _) if oui.map_or(true, |oui| oui == n) => (Assignment::MaL, n),
+                (Some(oui), 2, Some(start), Some(end)) if is_block(start, end, 20) => {
+                    (Assignment::MaM, (oui << 4) | (start >> 20))
+                }
+                (Some(oui), 2, Some(start), Some(end)) if is_block(start, end, 12) => {
+                    (Assignment::MaS, (oui << 12) | (start >> 12))
+                }
+                _ => return None,
+            };
+
+            registrations.extend(current.take().into_iter());
+            current = Some(Registration {
+                assignment: assignment,
+                prefix: prefix,
+                organization: organization.to_string(),
+                address: String::new(),
+            });
+        } else if line.starts_with(" ") || line.starts_with("\t") {
+            match current {
+                Some(ref mut registration) => {
+                    if !registration.address.is_empty() {
+                        registration.address.push(' ');
+                    }
+                    registration.address.push_str(line.trim());
+                }
+                None => {}
+            }
+        }
+    }
+    registrations.extend(current.take().into_iter());
+
+    Some(registrations)
+}
+
+/// `true` if `start-end` is an aligned block of `2^bits` values.
+fn is_block(start: u64, end: u64, bits: uint) -> bool {
+    let mask = (1 << bits) - 1;
+    start & mask == 0 && end == start | mask
+}
+
+/// Split a line of the text form around a marker, returning the trimmed text on each side.
+fn split_marker<'a>(line: &'a str, marker: &str) -> (&'a str, &'a str) {
+    let start = line.find_str(marker).unwrap();
+    (line.slice_to(start).trim(), line.slice_from(start + marker.len()).trim())
+}
+
+/// Parse the CSV form of a listing, with `Registry,Assignment,Organization Name,Organization
+/// Address` columns.
+fn parse_csv(data: &str) -> Option<Vec<Registration>> {
+    let mut registrations = Vec::new();
+
+    for line in data.lines_any().filter(|line| !line.trim().is_empty()).skip(1) {
+        let fields = match split_csv(line) {
+            Some(fields) => fields,
+            None => return None,
+        };
+        if fields.len() != 4 {
+            return None;
+        }
+
+        let assignment = match Assignment::from_name(fields[0].as_slice()) {
+            Some(assignment) => assignment,
+            None => return None,
+        };
+        let prefix = match parse_hex(fields[1].as_slice(), assignment.prefix_len() / 4) {
+            Some(prefix) => prefix,
+            None => return None,
+        };
+
+        registrations.push(Registration {
+            assignment: assignment,
+            prefix: prefix,
+            organization: fields[2].trim().to_string(),
+            address: fields[3].trim().to_string(),
+        });
+    }
+
+    Some(registrations)
+}
+
+/// Split a CSV record into its fields, which may be quoted to contain commas, with quotes
+/// escaped by doubling them.
+fn split_csv(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    loop {
+        let c = match chars.next() {
+            Some(c) => c,
+            None => break,
+        };
+
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(mem::replace(&mut field, String::new())),
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+/// Parse exactly `len` hexadecimal digits.
+fn parse_hex(s: &str, len: uint) -> Option<u64> {
+    if s.len() != len {
+        return None;
+    }
+
+    let mut n = 0u64;
+    for c in s.chars() {
+        match c.to_digit(16) {
+            Some(d) => n = (n << 4) | d as u64,
+            None => return None,
+        }
+    }
+    Some(n)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Assignment, Registry};
+    use eui::Eui48;
+
+    static MA_L_TXT: &'static str = "OUI/MA-L\t\t\tOrganization
+company_id\t\t\tOrganization
+\t\t\t\tAddress
+
+00-1B-77   (hex)\t\tIntel Corporate
+001B77     (base 16)\t\tIntel Corporate
+\t\t\t\tLot 8, Jalan Hi-Tech 2/3
+\t\t\t\tKulim  Kedah  09000
+\t\t\t\tMY
+
+70-B3-D5   (hex)\t\tIEEE Registration Authority
+70B3D5     (base 16)\t\tIEEE Registration Authority
+\t\t\t\t445 Hoes Lane
+\t\t\t\tPiscataway  NJ  08554
+\t\t\t\tUS
+";
+
+    static MA_S_TXT: &'static str = "OUI-36/MA-S\t\t\tOrganization
+company_id\t\t\tOrganization
+\t\t\t\tAddress
+
+70-B3-D5   (hex)\t\tExample Small Block Ltd
+F2F000-F2FFFF     (base 16)\t\tExample Small Block Ltd
+\t\t\t\tSomewhere
+
+70-B3-D5   (hex)\t\tExample Last Block Ltd
+AFF000-AFFFFF     (base 16)\t\tExample Last Block Ltd
+\t\t\t\tElsewhere
+";
+
+    static MA_M_CSV: &'static str = "Registry,Assignment,Organization Name,Organization Address
+MA-M,70B3D5F,\"Example Medium Block, Inc.\",\"1 Main Street, Springfield \"\"North\"\" US\"
+";
+
+    #[test]
+    fn test_parse_txt() {
+        let registry = Registry::parse(MA_L_TXT).unwrap();
+        assert_eq!(registry.len(), 2);
+
+        let registration = registry.lookup(Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd)).unwrap();
+        assert_eq!(registration.assignment, Assignment::MaL);
+        assert_eq!(registration.prefix, 0x001b77);
+        assert_eq!(registration.organization.as_slice(), "Intel Corporate");
+        assert_eq!(registration.address.as_slice(), "Lot 8, Jalan Hi-Tech 2/3 Kulim  Kedah  09000 MY");
+        assert_eq!(registration.first(), Eui48(0x00, 0x1b, 0x77, 0, 0, 0));
+
+        let registry = Registry::parse(MA_S_TXT).unwrap();
+        let registration = registry.lookup(Eui48(0x70, 0xb3, 0xd5, 0xf2, 0xf1, 0x23)).unwrap();
+        assert_eq!(registration.assignment, Assignment::MaS);
+        assert_eq!(registration.prefix, 0x70b3d5f2f);
+        assert!(registry.lookup(Eui48(0x70, 0xb3, 0xd5, 0xf3, 0xf1, 0x23)).is_none());
+
+        let registration = registry.lookup(Eui48(0x70, 0xb3, 0xd5, 0xaf, 0xf1, 0x23)).unwrap();
+        assert_eq!(registration.assignment, Assignment::MaS);
+        assert_eq!(registration.prefix, 0x70b3d5aff);
+        assert!(registry.lookup(Eui48(0x70, 0xb3, 0xd5, 0xa0, 0x00, 0x00)).is_none());
+
+        assert!(Registry::parse("00-1B-77   (hex)\t\tBroken\n001B7   (base 16)\t\tBroken\n").is_none());
+        assert!(Registry::parse("70-B3-D5   (hex)\t\tBroken\nF2F000-F2F0FF   (base 16)\t\tBroken\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let registry = Registry::parse(MA_M_CSV).unwrap();
+        let registration = registry.lookup(Eui48(0x70, 0xb3, 0xd5, 0xf0, 0, 1)).unwrap();
+        assert_eq!(registration.assignment, Assignment::MaM);
+        assert_eq!(registration.prefix, 0x70b3d5f);
+        assert_eq!(registration.organization.as_slice(), "Example Medium Block, Inc.");
+        assert_eq!(registration.address.as_slice(), "1 Main Street, Springfield \"North\" US");
+
+        assert!(Registry::parse("Registry,Assignment,Organization Name,Organization Address\nMA-M,70B3D5,X,Y\n").is_none());
+        assert!(Registry::parse("Registry,Assignment,Organization Name,Organization Address\nMA-L,001B77,\"X,Y\n").is_none());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut registry = Registry::parse(MA_L_TXT).unwrap();
+        registry.merge(Registry::parse(MA_M_CSV).unwrap());
+        registry.merge(Registry::parse(MA_S_TXT).unwrap());
+        assert_eq!(registry.len(), 5);
+
+        let organization = |eui: Eui48| registry.lookup(eui).map(|r| r.organization.clone());
+        assert_eq!(organization(Eui48(0x70, 0xb3, 0xd5, 0xf2, 0xf0, 0x00)),
+                   Some("Example Small Block Ltd".to_string()));
+        assert_eq!(organization(Eui48(0x70, 0xb3, 0xd5, 0xf3, 0x00, 0x00)),
+                   Some("Example Medium Block, Inc.".to_string()));
+        assert_eq!(organization(Eui48(0x70, 0xb3, 0xd5, 0x00, 0x00, 0x00)),
+                   Some("IEEE Registration Authority".to_string()));
+        assert_eq!(organization(Eui48(0x00, 0x00, 0x5e, 0x00, 0x00, 0x00)), None);
+    }
+
+    #[test]
+    fn test_embedded() {
+        let registry = Registry::embedded();
+        let registration = registry.lookup(Eui48(0x00, 0x50, 0x56, 0x01, 0x02, 0x03)).unwrap();
+        assert_eq!(registration.organization.as_slice(), "VMware, Inc.");
+    }
+}
@@ -0,0 +1,189 @@
+//! Renderers of blocklists for Linux and BSD firewalls.
+use addr::{ipv4, ipv6, IpAddrVersion, Ipv4, Ipv6};
+use net::{IpNetwork, Ipv4Network, Ipv6Network, Network};
+use super::select;
+
+/// The default maximum number of elements of an ipset set.
+const IPSET_MAXELEM: uint = 65536;
+
+/// Render an `ipset restore` script creating (or flushing) a `hash:net` set named `name`, and
+/// filling it with the networks of `version`.
+///
+/// `hash:net` sets can't hold a zero prefix length: a network covering every address is added as
+/// its two halves.
+pub fn ipset(name: &str, version: IpAddrVersion, networks: &[IpNetwork], collapse: bool) -> String {
+    let selected = select(networks, version, collapse);
+    let networks: Vec<IpNetwork> = selected.iter()
+                                           .flat_map(|net| halves(*net).into_iter())
+                                           .collect();
+    let family = match version {
+        Ipv4 => "inet",
+        Ipv6 => "inet6",
+    };
+
+    let mut out = format!("create {} hash:net family {}", name, family);
+    if networks.len() > IPSET_MAXELEM {
+        out.push_str(format!(" maxelem {}", networks.len()).as_slice());
+    }
+    out.push_str(" -exist\n");
+    out.push_str(format!("flush {}\n", name).as_slice());
+    for net in networks.iter() {
+        out.push_str(format!("add {} {}\n", name, net).as_slice());
+    }
+    out
+}
+
+/// Split a network with a zero prefix length in its two halves, and keep the others as they are.
+fn halves(net: IpNetwork) -> Vec<IpNetwork> {
+    match net {
+        _ if net.prefix() != 0 => vec![net],
+        Ipv4Network(_) => {
+            vec![Ipv4Network(Network(ipv4::IpAddr(0, 0, 0, 0), 1)),
+                 Ipv4Network(Network(ipv4::IpAddr(128, 0, 0, 0), 1))]
+        }
+        Ipv6Network(_) => {
+            vec![Ipv6Network(Network(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0, 0), 1)),
+                 Ipv6Network(Network(ipv6::IpAddr(0x8000, 0, 0, 0, 0, 0, 0, 0), 1))]
+        }
+    }
+}
+
+/// Render an nftables `set` block named `name`, with the interval flag, holding the networks of
+/// `version`. The block is meant to be included in a `table` block.
+///
+/// nftables rejects overlapping elements: networks which may overlap should be collapsed.
+pub fn nftables(name: &str, version: IpAddrVersion, networks: &[IpNetwork],
+                collapse: bool) -> String {
+    let networks = select(networks, version, collapse);
+    let kind = match version {
+        Ipv4 => "ipv4_addr",
+        Ipv6 => "ipv6_addr",
+    };
+
+    let mut out = format!("set {} {{\n\ttype {}\n\tflags interval\n", name, kind);
+    if !networks.is_empty() {
+        let elements: Vec<String> = networks.iter().map(|net| format!("\t\t{}", net)).collect();
+        out.push_str(format!("\telements = {{\n{}\n\t}}\n", elements.connect(",\n")).as_slice());
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render an `iptables-save` (or `ip6tables-save`, for IPv6) file declaring the user-defined
+/// chain `chain` in the `filter` table, with a rule jumping to `target` for each source network
+/// of `version`. If `parent` is given (e.g. `INPUT`), a rule jumping from it to `chain` is added.
+///
+/// The file must be loaded with `iptables-restore --noflush`: without it, every chain of the
+/// `filter` table is flushed. Loading it again flushes `chain`, but appends another jump rule to
+/// `parent`: reloads should pass no `parent`.
+pub fn iptables_save(chain: &str, target: &str, parent: Option<&str>, version: IpAddrVersion,
+                     networks: &[IpNetwork], collapse: bool) -> String {
+    let networks = select(networks, version, collapse);
+
+    let mut out = format!("*filter\n:{} - [0:0]\n", chain);
+    if let Some(parent) = parent {
+        out.push_str(format!("-A {} -j {}\n", parent, chain).as_slice());
+    }
+    for net in networks.iter() {
+        out.push_str(format!("-A {} -s {} -j {}\n", chain, net, target).as_slice());
+    }
+    out.push_str("COMMIT\n");
+    out
+}
+
+/// Render a pf table file, listing the networks of `version` one per line.
+pub fn pf_table(version: IpAddrVersion, networks: &[IpNetwork], collapse: bool) -> String {
+    let networks = select(networks, version, collapse);
+    networks.iter().map(|net| format!("{}\n", net)).collect::<Vec<String>>().concat()
+}
+
+#[cfg(test)]
+mod test {
+    use addr::{Ipv4, Ipv6, ipv4, ipv6};
+    use net::{IpNetwork, Ipv4Network, Ipv6Network, Network};
+    use super::{ipset, iptables_save, nftables, pf_table};
+
+    fn networks() -> Vec<IpNetwork> {
+        vec![Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 128), 25)),
+             Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 32)),
+             Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 0), 25)),
+             Ipv4Network(Network(ipv4::IpAddr(203, 0, 113, 7), 32))]
+    }
+
+    #[test]
+    fn test_ipset() {
+        assert_eq!(ipset("blocklist-v4", Ipv4, networks().as_slice(), true).as_slice(),
+                   "create blocklist-v4 hash:net family inet -exist
+flush blocklist-v4
+add blocklist-v4 192.0.2.0/24
+add blocklist-v4 203.0.113.7/32
+");
+        assert_eq!(ipset("blocklist-v6", Ipv6, networks().as_slice(), false).as_slice(),
+                   "create blocklist-v6 hash:net family inet6 -exist
+flush blocklist-v6
+add blocklist-v6 2001:db8::/32
+");
+
+        let networks = [Ipv4Network(Network(ipv4::IpAddr(0, 0, 0, 0), 1)),
+                        Ipv4Network(Network(ipv4::IpAddr(128, 0, 0, 0), 1)),
+                        Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 0), 24))];
+        assert_eq!(ipset("blocklist-v4", Ipv4, &networks, true).as_slice(),
+                   "create blocklist-v4 hash:net family inet -exist
+flush blocklist-v4
+add blocklist-v4 0.0.0.0/1
+add blocklist-v4 128.0.0.0/1
+");
+        let networks = [Ipv6Network(Network(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0, 0), 0))];
+        assert_eq!(ipset("blocklist-v6", Ipv6, &networks, false).as_slice(),
+                   "create blocklist-v6 hash:net family inet6 -exist
+flush blocklist-v6
+add blocklist-v6 ::/1
+add blocklist-v6 8000::/1
+");
+    }
+
+    #[test]
+    fn test_nftables() {
+        assert_eq!(nftables("blocklist", Ipv4, networks().as_slice(), false).as_slice(),
+                   "set blocklist {
+\ttype ipv4_addr
+\tflags interval
+\telements = {
+\t\t192.0.2.128/25,
+\t\t192.0.2.0/25,
+\t\t203.0.113.7/32
+\t}
+}
+");
+        assert_eq!(nftables("blocklist6", Ipv6, &[], true).as_slice(),
+                   "set blocklist6 {\n\ttype ipv6_addr\n\tflags interval\n}\n");
+    }
+
+    #[test]
+    fn test_iptables_save() {
+        let rules = iptables_save("BLOCKLIST", "DROP", None, Ipv4, networks().as_slice(), true);
+        assert_eq!(rules.as_slice(),
+                   "*filter
+:BLOCKLIST - [0:0]
+-A BLOCKLIST -s 192.0.2.0/24 -j DROP
+-A BLOCKLIST -s 203.0.113.7/32 -j DROP
+COMMIT
+");
+        let rules = iptables_save("BLOCKLIST", "DROP", Some("INPUT"), Ipv6,
+                                  networks().as_slice(), false);
+        assert_eq!(rules.as_slice(),
+                   "*filter
+:BLOCKLIST - [0:0]
+-A INPUT -j BLOCKLIST
+-A BLOCKLIST -s 2001:db8::/32 -j DROP
+COMMIT
+");
+    }
+
+    #[test]
+    fn test_pf_table() {
+        assert_eq!(pf_table(Ipv6, networks().as_slice(), true).as_slice(), "2001:db8::/32\n");
+        assert_eq!(pf_table(Ipv4, networks().as_slice(), true).as_slice(),
+                   "192.0.2.0/24\n203.0.113.7/32\n");
+    }
+}
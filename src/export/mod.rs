@@ -0,0 +1,30 @@
+//! Render lists of networks as configuration for firewalls and routers.
+//!
+//! Most configuration formats keep address families apart, so each renderer outputs the networks
+//! of a single family, and is called once per family. Networks are rendered without their host
+//! bits, and may be collapsed first (see `net::collapse_addresses`).
+use addr::IpAddrVersion;
+use net::{self, IpNetwork, Ipv4Network, Ipv6Network, Network};
+
+pub mod firewall;
+pub mod router;
+
+/// Select the networks of `version`, without their host bits, collapsing them if asked.
+fn select(networks: &[IpNetwork], version: IpAddrVersion, collapse: bool) -> Vec<IpNetwork> {
+    let selected: Vec<IpNetwork> = networks.iter()
+                                           .filter(|net| net.version() == version)
+                                           .map(|net| strip(*net))
+                                           .collect();
+    if collapse {
+        net::collapse_addresses(selected.as_slice())
+    } else {
+        selected
+    }
+}
+
+fn strip(net: IpNetwork) -> IpNetwork {
+    match net {
+        Ipv4Network(net) => Ipv4Network(Network(net.address(), net.prefix())),
+        Ipv6Network(net) => Ipv6Network(Network(net.address(), net.prefix())),
+    }
+}
@@ -0,0 +1,332 @@
+//! Renderers of prefix lists and access lists for routers.
+use std::cmp;
+use addr::{ipv4, ipv6, IpAddrVersion, Ipv4, Ipv6};
+use net::{IpNetwork, Ipv4Network, Ipv6Network};
+use super::select;
+
+/// The action of a prefix or access list entry.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub enum Action {
+    Permit,
+    Deny,
+}
+
+impl Action {
+    fn keyword(&self) -> &'static str {
+        match *self {
+            Action::Permit => "permit",
+            Action::Deny => "deny",
+        }
+    }
+}
+
+/// The lengths of the prefixes matched by each network of a prefix list, as with the `ge` and
+/// `le` keywords of Cisco IOS.
+///
+/// The range is narrowed to the lengths which can match under each network: `le` is capped to
+/// the bit width of the addresses. Networks under which no length of the range can match (e.g.
+/// a `/32` with `le 28`, or any network with `ge` above `le`) are left out of the list.
+///
+/// Aggregation never changes the prefixes matched by a list: networks are only collapsed when
+/// the range matches the same lengths under each of them.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub struct LengthRange {
+    /// Match prefixes at least this long.
+    pub ge: Option<uint>,
+    /// Match prefixes at most this long.
+    pub le: Option<uint>,
+}
+
+impl LengthRange {
+    /// Only match the networks themselves.
+    pub fn exact() -> LengthRange {
+        LengthRange {
+            ge: None,
+            le: None,
+        }
+    }
+
+    /// Match the networks and all their subnets.
+    pub fn or_longer() -> LengthRange {
+        LengthRange {
+            ge: None,
+            le: Some(ipv6::MAX_PREFIXLEN),
+        }
+    }
+
+    /// Get the first and last prefix lengths matched under `net`, or `None` if no prefix
+    /// length under `net` is in the range.
+    ///
+    /// As with Cisco IOS, a missing `ge` stands for the prefix length of `net`, and a missing
+    /// `le` for the bit width of the addresses if `ge` is set, or for the prefix length of `net`.
+    fn lengths(&self, net: IpNetwork) -> Option<(uint, uint)> {
+        let prefix = net.prefix();
+        let first = cmp::max(self.ge.unwrap_or(prefix), prefix);
+        let last = match (self.ge, self.le) {
+            (_, Some(le)) => cmp::min(le, max_prefixlen(net)),
+            (Some(_), None) => max_prefixlen(net),
+            (None, None) => prefix,
+        };
+        if first > last { None } else { Some((first, last)) }
+    }
+
+    /// Get the networks of `version` with the `ge` and `le` bounds which apply to them, leaving
+    /// out the networks under which no length of the range can match.
+    ///
+    /// When aggregating, a collapsed network is only used if all the networks it merges match
+    /// the same lengths, with its `ge` set to them: the two halves of a `/24` with `le 28` become
+    /// the `/24` with `ge 25 le 28`. Otherwise the merged networks are listed on their own.
+    fn entries(&self, networks: &[IpNetwork], version: IpAddrVersion, aggregate: bool)
+               -> Vec<(IpNetwork, Option<uint>, Option<uint>)> {
+        let selected = select(networks, version, false);
+        let mut entries = vec![];
+        if !aggregate {
+            for net in selected.iter() {
+                if let Some((first, last)) = self.lengths(*net) {
+                    entries.push(bounds(*net, first, last));
+                }
+            }
+            return entries;
+        }
+
+        for collapsed in select(networks, version, true).iter() {
+            let merged: Vec<IpNetwork> = selected.iter()
+                                                 .filter(|net| net.is_subnet_of(*collapsed))
+                                                 .map(|net| *net)
+                                                 .collect();
+            let ranges: Vec<Option<(uint, uint)>> = merged.iter()
+                                                          .map(|net| self.lengths(*net))
+                                                          .collect();
+            if ranges.iter().all(|range| *range == ranges[0]) {
+                if let Some((first, last)) = ranges[0] {
+                    entries.push(bounds(*collapsed, first, last));
+                }
+            } else {
+                for (net, range) in merged.iter().zip(ranges.iter()) {
+                    if let Some((first, last)) = *range {
+                        entries.push(bounds(*net, first, last));
+                    }
+                }
+            }
+        }
+        entries
+    }
+}
+
+fn max_prefixlen(net: IpNetwork) -> uint {
+    match net {
+        Ipv4Network(_) => ipv4::MAX_PREFIXLEN,
+        Ipv6Network(_) => ipv6::MAX_PREFIXLEN,
+    }
+}
+
+/// Get the `ge` and `le` keywords matching the prefix lengths from `first` to `last` under `net`,
+/// leaving out the ones implied by the others.
+fn bounds(net: IpNetwork, first: uint, last: uint) -> (IpNetwork, Option<uint>, Option<uint>) {
+    let ge = if first > net.prefix() { Some(first) } else { None };
+    let implied_le = if ge.is_some() { max_prefixlen(net) } else { net.prefix() };
+    let le = if last != implied_le { Some(last) } else { None };
+    (net, ge, le)
+}
+
+/// Render a Cisco IOS `ip prefix-list` (or `ipv6 prefix-list`, for IPv6) named `name`, with an
+/// entry for each network of `version` under which `lengths` can match, numbered by steps of 5.
+pub fn cisco_prefix_list(name: &str, action: Action, version: IpAddrVersion,
+                         networks: &[IpNetwork], lengths: LengthRange, aggregate: bool) -> String {
+    let command = match version {
+        Ipv4 => "ip",
+        Ipv6 => "ipv6",
+    };
+
+    let mut out = String::new();
+    let mut seq = 0;
+    for &(net, ge, le) in lengths.entries(networks, version, aggregate).iter() {
+        seq += 5;
+        out.push_str(format!("{} prefix-list {} seq {} {} {}", command, name, seq,
+                             action.keyword(), net).as_slice());
+        if let Some(ge) = ge {
+            out.push_str(format!(" ge {}", ge).as_slice());
+        }
+        if let Some(le) = le {
+            out.push_str(format!(" le {}", le).as_slice());
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Render a Cisco IOS access list named `name`, with an entry for each source network of
+/// `version`.
+///
+/// IPv4 networks are written with wildcard masks in a standard access list; IPv6 networks are
+/// written in prefix form, as IPv6 access lists don't support wildcard masks.
+pub fn cisco_acl(name: &str, action: Action, version: IpAddrVersion, networks: &[IpNetwork],
+                 aggregate: bool) -> String {
+    let mut out = match version {
+        Ipv4 => format!("ip access-list standard {}\n", name),
+        Ipv6 => format!("ipv6 access-list {}\n", name),
+    };
+
+    for net in select(networks, version, aggregate).iter() {
+        let source = match *net {
+            _ if net.prefix() == 0 => "any".to_string(),
+            Ipv4Network(net) if net.prefix() == ipv4::MAX_PREFIXLEN => {
+                format!("host {}", net.address())
+            }
+            Ipv4Network(net) => format!("{} {}", net.address(), net.hostmask()),
+            Ipv6Network(net) if net.prefix() == ipv6::MAX_PREFIXLEN => {
+                format!("host {}", net.address())
+            }
+            Ipv6Network(net) => format!("{}", net),
+        };
+
+        match version {
+            Ipv4 => out.push_str(format!(" {} {}\n", action.keyword(), source).as_slice()),
+            Ipv6 => {
+                out.push_str(format!(" {} ipv6 {} any\n", action.keyword(), source).as_slice())
+            }
+        }
+    }
+    out
+}
+
+/// Render a Juniper `prefix-list` stanza named `name`, holding the networks of `version`.
+pub fn juniper_prefix_list(name: &str, version: IpAddrVersion, networks: &[IpNetwork],
+                           aggregate: bool) -> String {
+    let mut out = format!("prefix-list {} {{\n", name);
+    for net in select(networks, version, aggregate).iter() {
+        out.push_str(format!("    {};\n", net).as_slice());
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render Juniper `route-filter` statements for the networks of `version`, to be included in the
+/// `from` clause of a policy term.
+pub fn juniper_route_filters(version: IpAddrVersion, networks: &[IpNetwork], lengths: LengthRange,
+                             aggregate: bool) -> String {
+    let mut out = String::new();
+    for &(net, ge, le) in lengths.entries(networks, version, aggregate).iter() {
+        let max = max_prefixlen(net);
+        let kind = match (ge, le) {
+            (None, None) => "exact".to_string(),
+            (None, Some(le)) if le == max => "orlonger".to_string(),
+            (None, Some(le)) => format!("upto /{}", le),
+            (Some(ge), le) => format!("prefix-length-range /{}-/{}", ge, le.unwrap_or(max)),
+        };
+        out.push_str(format!("route-filter {} {};\n", net, kind).as_slice());
+    }
+    out
+}
+
+/// Render a BIRD prefix set constant named `name`, holding the networks of `version`.
+pub fn bird_prefix_set(name: &str, version: IpAddrVersion, networks: &[IpNetwork],
+                       lengths: LengthRange, aggregate: bool) -> String {
+    let entries = lengths.entries(networks, version, aggregate);
+    let prefixes: Vec<String> = entries.iter().map(|&(net, ge, le)| {
+        let max = max_prefixlen(net);
+        match (ge, le) {
+            (None, None) => format!("    {}", net),
+            (None, Some(le)) if le == max => format!("    {}+", net),
+            (ge, le) => {
+                let ge = ge.unwrap_or(net.prefix());
+                format!("    {}{{{},{}}}", net, ge, le.unwrap_or(max))
+            }
+        }
+    }).collect();
+
+    format!("define {} = [\n{}\n];\n", name, prefixes.connect(",\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use addr::{Ipv4, Ipv6, ipv4, ipv6};
+    use net::{IpNetwork, Ipv4Network, Ipv6Network, Network};
+    use super::{bird_prefix_set, cisco_acl, cisco_prefix_list, juniper_prefix_list,
+                juniper_route_filters, Action, LengthRange};
+
+    fn networks() -> Vec<IpNetwork> {
+        vec![Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 0), 25)),
+             Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 128), 25)),
+             Ipv4Network(Network(ipv4::IpAddr(203, 0, 113, 7), 32)),
+             Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32))]
+    }
+
+    #[test]
+    fn test_cisco_prefix_list() {
+        let lengths = LengthRange { ge: Some(26), le: Some(28) };
+        assert_eq!(cisco_prefix_list("CUSTOMERS", Action::Permit, Ipv4, networks().as_slice(),
+                                     lengths, true).as_slice(),
+                   "ip prefix-list CUSTOMERS seq 5 permit 192.0.2.0/24 ge 26 le 28\n");
+        assert_eq!(cisco_prefix_list("CUSTOMERS", Action::Permit, Ipv4, networks().as_slice(),
+                                     LengthRange { ge: Some(20), le: None }, false).as_slice(),
+                   "ip prefix-list CUSTOMERS seq 5 permit 192.0.2.0/25 le 32
+ip prefix-list CUSTOMERS seq 10 permit 192.0.2.128/25 le 32
+ip prefix-list CUSTOMERS seq 15 permit 203.0.113.7/32
+");
+        assert_eq!(cisco_prefix_list("CUSTOMERS", Action::Permit, Ipv4, networks().as_slice(),
+                                     LengthRange::exact(), true).as_slice(),
+                   "ip prefix-list CUSTOMERS seq 5 permit 192.0.2.0/24 ge 25 le 25
+ip prefix-list CUSTOMERS seq 10 permit 203.0.113.7/32
+");
+        let lengths = LengthRange { ge: Some(30), le: Some(28) };
+        assert_eq!(cisco_prefix_list("CUSTOMERS", Action::Deny, Ipv4, networks().as_slice(),
+                                     lengths, true).as_slice(), "");
+        assert_eq!(cisco_prefix_list("CUSTOMERS", Action::Deny, Ipv6, networks().as_slice(),
+                                     LengthRange::or_longer(), false).as_slice(),
+                   "ipv6 prefix-list CUSTOMERS seq 5 deny 2001:db8::/32 le 128\n");
+    }
+
+    #[test]
+    fn test_cisco_acl() {
+        let acl = cisco_acl("BLOCKLIST", Action::Deny, Ipv4, networks().as_slice(), false);
+        assert_eq!(acl.as_slice(),
+                   "ip access-list standard BLOCKLIST
+ deny 192.0.2.0 0.0.0.127
+ deny 192.0.2.128 0.0.0.127
+ deny host 203.0.113.7
+");
+        let acl = cisco_acl("BLOCKLIST", Action::Deny, Ipv6, networks().as_slice(), false);
+        assert_eq!(acl.as_slice(),
+                   "ipv6 access-list BLOCKLIST\n deny ipv6 2001:db8::/32 any\n");
+    }
+
+    #[test]
+    fn test_juniper() {
+        assert_eq!(juniper_prefix_list("customers", Ipv4, networks().as_slice(), true).as_slice(),
+                   "prefix-list customers {
+    192.0.2.0/24;
+    203.0.113.7/32;
+}
+");
+
+        let lengths = LengthRange { ge: None, le: Some(28) };
+        assert_eq!(juniper_route_filters(Ipv4, networks().as_slice(), lengths, true).as_slice(),
+                   "route-filter 192.0.2.0/24 prefix-length-range /25-/28;\n");
+
+        // The /25 and the /26s match different lengths, so they can't be aggregated.
+        let mixed = [Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 0), 25)),
+                     Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 128), 26)),
+                     Ipv4Network(Network(ipv4::IpAddr(192, 0, 2, 192), 26))];
+        assert_eq!(juniper_route_filters(Ipv4, &mixed, lengths, true).as_slice(),
+                   "route-filter 192.0.2.0/25 upto /28;
+route-filter 192.0.2.128/26 upto /28;
+route-filter 192.0.2.192/26 upto /28;
+");
+        let lengths = LengthRange { ge: Some(48), le: None };
+        assert_eq!(juniper_route_filters(Ipv6, networks().as_slice(), lengths, true).as_slice(),
+                   "route-filter 2001:db8::/32 prefix-length-range /48-/128;\n");
+    }
+
+    #[test]
+    fn test_bird_prefix_set() {
+        let lengths = LengthRange { ge: None, le: Some(28) };
+        let set = bird_prefix_set("customers", Ipv4, networks().as_slice(), lengths, true);
+        assert_eq!(set.as_slice(),
+                   "define customers = [\n    192.0.2.0/24{25,28}\n];\n");
+        let set = bird_prefix_set("customers6", Ipv6, networks().as_slice(),
+                                  LengthRange::or_longer(), true);
+        assert_eq!(set.as_slice(),
+                   "define customers6 = [\n    2001:db8::/32+\n];\n");
+    }
+}
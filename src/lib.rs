@@ -5,9 +5,24 @@
 #[macro_use]
 extern crate log;
 extern crate "rustc-serialize" as rustc_serialize;
+#[cfg(feature = "rand")]
+extern crate rand;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 pub use addr::IpAddr;
+pub use asn::Asn;
 pub use net::IpNetwork;
+pub use eui::{Eui48, Eui64};
 
 pub mod addr;
+pub mod asn;
+pub mod eui;
+pub mod export;
+pub mod mmdb;
 pub mod net;
+pub mod rir;
+pub mod wire;
+mod sha256;
+#[cfg(feature = "serde")]
+pub mod serialize;
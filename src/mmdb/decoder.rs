@@ -0,0 +1,240 @@
+//! Decoder of the data section (and of the metadata section, which uses the same format).
+use std::cmp;
+use std::collections::BTreeMap;
+use std::mem;
+use super::{MmdbError, Value};
+
+/// Maximum nesting of maps, arrays and pointers, to stop on pointer loops.
+const MAX_DEPTH: uint = 64;
+
+const POINTER: u8 = 1;
+const STRING: u8 = 2;
+const DOUBLE: u8 = 3;
+const BYTES: u8 = 4;
+const UINT16: u8 = 5;
+const UINT32: u8 = 6;
+const MAP: u8 = 7;
+const INT32: u8 = 8;
+const UINT64: u8 = 9;
+const UINT128: u8 = 10;
+const ARRAY: u8 = 11;
+const BOOLEAN: u8 = 14;
+const FLOAT: u8 = 15;
+
+pub struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    /// Create a decoder of a section, which pointers are relative to.
+    pub fn new(data: &'a [u8]) -> Decoder<'a> {
+        Decoder {
+            data: data,
+        }
+    }
+
+    /// Decode the value at `offset`, returning it with the offset following it.
+    pub fn decode(&self, offset: uint) -> Result<(Value, uint), MmdbError> {
+        self.decode_at(offset, 0)
+    }
+
+    fn decode_at(&self, offset: uint, depth: uint) -> Result<(Value, uint), MmdbError> {
+        if depth > MAX_DEPTH {
+            return Err(MmdbError::InvalidDatabase("data nested too deeply"));
+        }
+
+        let mut pos = offset;
+        let ctrl = try!(self.take(&mut pos, 1))[0];
+        let mut kind = ctrl >> 5;
+
+        if kind == POINTER {
+            let size = ((ctrl >> 3) & 0x3) as uint;
+            let high = (ctrl & 0x7) as uint;
+            let low = be(try!(self.take(&mut pos, size + 1))) as uint;
+            let pointer = match size {
+                0 => (high << 8) | low,
+                1 => ((high << 16) | low) + 2048,
+                2 => ((high << 24) | low) + 526336,
+                _ => low,
+            };
+
+            let (value, _) = try!(self.decode_at(pointer, depth + 1));
+            return Ok((value, pos));
+        }
+
+        if kind == 0 {
+            kind = 7 + try!(self.take(&mut pos, 1))[0];
+        }
+
+        let mut size = (ctrl & 0x1f) as uint;
+        if size >= 29 {
+            let n = be(try!(self.take(&mut pos, size - 28))) as uint;
+            size = match size {
+                29 => 29 + n,
+                30 => 285 + n,
+                _ => 65821 + n,
+            };
+        }
+
+        let value = match kind {
+            MAP => {
+                let mut map = BTreeMap::new();
+                for _ in range(0, size) {
+                    let (key, next) = try!(self.decode_at(pos, depth + 1));
+                    let key = match key {
+                        Value::String(key) => key,
+                        _ => return Err(MmdbError::InvalidDatabase("map key isn't a string")),
+                    };
+                    let (value, next) = try!(self.decode_at(next, depth + 1));
+                    map.insert(key, value);
+                    pos = next;
+                }
+                Value::Map(map)
+            }
+            ARRAY => {
+                // Each element takes at least a byte: don't trust larger sizes.
+                let mut array = Vec::with_capacity(cmp::min(size, self.data.len() - pos));
+                for _ in range(0, size) {
+                    let (value, next) = try!(self.decode_at(pos, depth + 1));
+                    array.push(value);
+                    pos = next;
+                }
+                Value::Array(array)
+            }
+            BOOLEAN => match size {
+                0 => Value::Boolean(false),
+                1 => Value::Boolean(true),
+                _ => return Err(MmdbError::InvalidDatabase("invalid boolean")),
+            },
+            _ => {
+                let bytes = try!(self.take(&mut pos, size));
+                try!(decode_scalar(kind, bytes))
+            }
+        };
+        Ok((value, pos))
+    }
+
+    fn take(&self, pos: &mut uint, len: uint) -> Result<&'a [u8], MmdbError> {
+        if *pos > self.data.len() || self.data.len() - *pos < len {
+            return Err(MmdbError::InvalidDatabase("unexpected end of data"));
+        }
+
+        let bytes = &self.data[*pos..*pos + len];
+        *pos += len;
+        Ok(bytes)
+    }
+}
+
+fn decode_scalar(kind: u8, bytes: &[u8]) -> Result<Value, MmdbError> {
+    let max_len = match kind {
+        DOUBLE => 8,
+        FLOAT => 4,
+        UINT16 => 2,
+        UINT32 | INT32 => 4,
+        UINT64 => 8,
+        UINT128 => 16,
+        _ => bytes.len(),
+    };
+    let exact = kind == DOUBLE || kind == FLOAT;
+    if bytes.len() > max_len || (exact && bytes.len() != max_len) {
+        return Err(MmdbError::InvalidDatabase("invalid value size"));
+    }
+
+    let value = match kind {
+        STRING => match String::from_utf8(bytes.to_vec()) {
+            Ok(s) => Value::String(s),
+            Err(_) => return Err(MmdbError::InvalidDatabase("invalid UTF-8 string")),
+        },
+        DOUBLE => Value::Double(unsafe { mem::transmute::<u64, f64>(be(bytes)) }),
+        FLOAT => Value::Float(unsafe { mem::transmute::<u32, f32>(be(bytes) as u32) }),
+        BYTES => Value::Bytes(bytes.to_vec()),
+        UINT16 => Value::Uint16(be(bytes) as u16),
+        UINT32 => Value::Uint32(be(bytes) as u32),
+        INT32 => Value::Int32(be(bytes) as u32 as i32),
+        UINT64 => Value::Uint64(be(bytes)),
+        UINT128 => {
+            let split = if bytes.len() > 8 { bytes.len() - 8 } else { 0 };
+            Value::Uint128([be(&bytes[..split]), be(&bytes[split..])])
+        }
+        _ => return Err(MmdbError::InvalidDatabase("unknown data type")),
+    };
+    Ok(value)
+}
+
+/// Decode a big-endian unsigned integer of at most 8 octets.
+pub fn be(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |n, &b| (n << 8) | b as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use mmdb::{MmdbError, Value};
+    use super::Decoder;
+
+    fn decode(data: &[u8]) -> Result<Value, MmdbError> {
+        Decoder::new(data).decode(0).map(|(value, _)| value)
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(decode(&[0x43, b'a', b'b', b'c']), Ok(Value::String("abc".to_string())));
+        assert_eq!(decode(&[0xa0]), Ok(Value::Uint16(0)));
+        assert_eq!(decode(&[0xa2, 0x01, 0x02]), Ok(Value::Uint16(0x102)));
+        assert_eq!(decode(&[0xc4, 0xff, 0xff, 0xff, 0xff]), Ok(Value::Uint32(0xffffffff)));
+        assert_eq!(decode(&[0x04, 0x01, 0xff, 0xff, 0xff, 0xfe]), Ok(Value::Int32(-2)));
+        assert_eq!(decode(&[0x01, 0x01, 0x2a]), Ok(Value::Int32(42)));
+        assert_eq!(decode(&[0x02, 0x02, 0x01, 0x00]), Ok(Value::Uint64(256)));
+        assert_eq!(decode(&[0x09, 0x03, 1, 0, 0, 0, 0, 0, 0, 0, 2]), Ok(Value::Uint128([1, 2])));
+        assert_eq!(decode(&[0x01, 0x07]), Ok(Value::Boolean(true)));
+        assert_eq!(decode(&[0x00, 0x07]), Ok(Value::Boolean(false)));
+        assert_eq!(decode(&[0x68, 0x40, 0x09, 0x21, 0xfb, 0x54, 0x44, 0x2d, 0x18]),
+                   Ok(Value::Double(3.141592653589793)));
+        assert_eq!(decode(&[0x04, 0x08, 0x3f, 0x80, 0x00, 0x00]), Ok(Value::Float(1.0)));
+        assert_eq!(decode(&[0x82, 0x00, 0xff]), Ok(Value::Bytes(vec![0x00, 0xff])));
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut data = vec![0x5d, 1];
+        data.push_all(Vec::from_elem(30, b'x').as_slice());
+        assert_eq!(decode(data.as_slice()).unwrap().as_str().map(|s| s.len()), Some(30));
+
+        let mut data = vec![0x5e, 0, 1];
+        data.push_all(Vec::from_elem(286, b'x').as_slice());
+        assert_eq!(decode(data.as_slice()).unwrap().as_str().map(|s| s.len()), Some(286));
+
+        assert!(decode(&[0x44, b'a']).is_err());
+        assert!(decode(&[0xa3, 0, 0, 0]).is_err());
+        assert!(decode(&[0x63, 0, 0, 0]).is_err());
+        assert!(decode(&[0x42, 0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn test_containers() {
+        // {"en": "Test", "ids": [1, 2]}
+        let data = [0xe2, 0x42, b'e', b'n', 0x44, b'T', b'e', b's', b't',
+                    0x43, b'i', b'd', b's', 0x02, 0x04, 0xa1, 0x01, 0xa1, 0x02];
+        let value = decode(&data).unwrap();
+        assert_eq!(value.get("en").and_then(|v| v.as_str()), Some("Test"));
+        assert_eq!(value.get("ids"), Some(&Value::Array(vec![Value::Uint16(1), Value::Uint16(2)])));
+
+        assert!(decode(&[0xe1, 0xa1, 0x01, 0xa1, 0x02]).is_err());
+        // An array of 16843036 elements, without any.
+        assert!(decode(&[0x1f, 0x04, 0xff, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_pointers() {
+        // A map whose key and value are pointers to the strings at offsets 8 and 11.
+        let data = [0xe1, 0x20, 0x08, 0x20, 0x0b, 0, 0, 0, 0x42, b'e', b'n', 0x42, b'f', b'r'];
+        let mut map = BTreeMap::new();
+        map.insert("en".to_string(), Value::String("fr".to_string()));
+        assert_eq!(Decoder::new(&data).decode(0), Ok((Value::Map(map), 5)));
+
+        assert_eq!(Decoder::new(&data).decode(3), Ok((Value::String("fr".to_string()), 5)));
+
+        // A pointer to itself.
+        assert!(decode(&[0x20, 0x00]).is_err());
+    }
+}
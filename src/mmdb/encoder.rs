@@ -0,0 +1,134 @@
+//! Encoder of the data section, the inverse of the decoder.
+use std::mem;
+use super::{MmdbError, Value};
+
+/// Append the encoding of `value` to `buf`.
+pub fn encode(value: &Value, buf: &mut Vec<u8>) -> Result<(), MmdbError> {
+    match *value {
+        Value::String(ref s) => {
+            try!(control(2, s.len(), buf));
+            buf.push_all(s.as_bytes());
+        }
+        Value::Double(n) => {
+            try!(control(3, 8, buf));
+            push_uint(unsafe { mem::transmute::<f64, u64>(n) }, 8, buf);
+        }
+        Value::Bytes(ref bytes) => {
+            try!(control(4, bytes.len(), buf));
+            buf.push_all(bytes.as_slice());
+        }
+        Value::Uint16(n) => try!(uint(5, n as u64, buf)),
+        Value::Uint32(n) => try!(uint(6, n as u64, buf)),
+        Value::Map(ref map) => {
+            try!(control(7, map.len(), buf));
+            for (key, value) in map.iter() {
+                try!(control(2, key.len(), buf));
+                buf.push_all(key.as_bytes());
+                try!(encode(value, buf));
+            }
+        }
+        Value::Int32(n) if n < 0 => {
+            try!(control(8, 4, buf));
+            push_uint(n as u32 as u64, 4, buf);
+        }
+        Value::Int32(n) => try!(uint(8, n as u64, buf)),
+        Value::Uint64(n) => try!(uint(9, n, buf)),
+        Value::Uint128([0, lo]) => try!(uint(10, lo, buf)),
+        Value::Uint128([hi, lo]) => {
+            let len = uint_len(hi);
+            try!(control(10, len + 8, buf));
+            push_uint(hi, len, buf);
+            push_uint(lo, 8, buf);
+        }
+        Value::Array(ref array) => {
+            try!(control(11, array.len(), buf));
+            for value in array.iter() {
+                try!(encode(value, buf));
+            }
+        }
+        Value::Boolean(b) => try!(control(14, b as uint, buf)),
+        Value::Float(n) => {
+            try!(control(15, 4, buf));
+            push_uint(unsafe { mem::transmute::<f32, u32>(n) } as u64, 4, buf);
+        }
+    }
+    Ok(())
+}
+
+/// Append the control byte(s) of a value of type `kind` and size `size`.
+fn control(kind: u8, size: uint, buf: &mut Vec<u8>) -> Result<(), MmdbError> {
+    let (bits, extra, len) = match size {
+        0...28 => (size as u8, 0, 0),
+        29...284 => (29, size - 29, 1),
+        285...65820 => (30, size - 285, 2),
+        65821...16843036 => (31, size - 65821, 3),
+        _ => return Err(MmdbError::ValueTooLarge),
+    };
+
+    if kind <= 7 {
+        buf.push(kind << 5 | bits);
+    } else {
+        buf.push(bits);
+        buf.push(kind - 7);
+    }
+    push_uint(extra as u64, len, buf);
+    Ok(())
+}
+
+/// Append an unsigned integer of type `kind`, in as few octets as possible.
+fn uint(kind: u8, n: u64, buf: &mut Vec<u8>) -> Result<(), MmdbError> {
+    let len = uint_len(n);
+    try!(control(kind, len, buf));
+    push_uint(n, len, buf);
+    Ok(())
+}
+
+fn uint_len(n: u64) -> uint {
+    range(0, 8).find(|&len| n >> (8 * len) == 0).unwrap_or(8)
+}
+
+/// Append the `len` least significant octets of `n`, in big-endian order.
+pub fn push_uint(n: u64, len: uint, buf: &mut Vec<u8>) {
+    buf.extend(range(0, len).rev().map(|i| (n >> (8 * i)) as u8));
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use mmdb::decoder::Decoder;
+    use mmdb::Value;
+    use super::encode;
+
+    #[test]
+    fn test_round_trip() {
+        let name = String::from_utf8(Vec::from_elem(300, b'x')).unwrap();
+        let mut map = BTreeMap::new();
+        map.insert("name".to_string(), Value::String(name));
+        map.insert("ids".to_string(), Value::Array(vec![Value::Uint16(1), Value::Uint32(70000)]));
+        map.insert("negative".to_string(), Value::Int32(-2));
+        map.insert("positive".to_string(), Value::Int32(42));
+        map.insert("big".to_string(), Value::Uint64(1 << 40));
+        map.insert("huge".to_string(), Value::Uint128([1, 2]));
+        map.insert("small".to_string(), Value::Uint128([0, 2]));
+        map.insert("pi".to_string(), Value::Double(3.141592653589793));
+        map.insert("one".to_string(), Value::Float(1.0));
+        map.insert("yes".to_string(), Value::Boolean(true));
+        map.insert("raw".to_string(), Value::Bytes(vec![0, 0xff]));
+        let value = Value::Map(map);
+
+        let mut buf = Vec::new();
+        encode(&value, &mut buf).unwrap();
+        assert_eq!(Decoder::new(buf.as_slice()).decode(0), Ok((value, buf.len())));
+    }
+
+    #[test]
+    fn test_encoding() {
+        let mut buf = Vec::new();
+        encode(&Value::Uint16(0), &mut buf).unwrap();
+        encode(&Value::Uint32(0x102), &mut buf).unwrap();
+        encode(&Value::Boolean(false), &mut buf).unwrap();
+        encode(&Value::Int32(-1), &mut buf).unwrap();
+        assert_eq!(buf, vec![0xa0, 0xc2, 0x01, 0x02, 0x00, 0x07,
+                             0x04, 0x01, 0xff, 0xff, 0xff, 0xff]);
+    }
+}
@@ -0,0 +1,119 @@
+//! Read and write MaxMind DB files (`.mmdb`), as used by the GeoIP2 and GeoLite2 databases.
+//!
+//! A database maps networks to data records, through a binary search tree walked with the bits of
+//! an address. The format is described at <http://maxmind.github.io/MaxMind-DB/>.
+use std::collections::BTreeMap;
+use std::error::{Error, FromError};
+use std::fmt;
+use std::io::IoError;
+pub use self::reader::{Metadata, Networks, Reader};
+pub use self::writer::{RecordSize, Writer};
+
+mod decoder;
+mod encoder;
+pub mod reader;
+pub mod writer;
+
+/// The marker preceding the metadata section, at the end of a database.
+static METADATA_MARKER: &'static [u8] = b"\xab\xcd\xefMaxMind.com";
+
+/// A value of the data section.
+#[derive(Clone, Show, PartialEq)]
+pub enum Value {
+    String(String),
+    Double(f64),
+    Bytes(Vec<u8>),
+    Uint16(u16),
+    Uint32(u32),
+    Map(BTreeMap<String, Value>),
+    Int32(i32),
+    Uint64(u64),
+    /// A 128-bits unsigned integer, represented as two `u64` (most significant first).
+    Uint128([u64; 2]),
+    Array(Vec<Value>),
+    Boolean(bool),
+    Float(f32),
+}
+
+impl Value {
+    /// Get the value of `key`, if this value is a map.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match *self {
+            Value::Map(ref map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Get the string, if this value is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Get the integer, if this value is an unsigned integer of at most 64 bits.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Uint16(n) => Some(n as u64),
+            Value::Uint32(n) => Some(n as u64),
+            Value::Uint64(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+/// Get the bit at `index` (0 being the least significant) of a 128-bits integer.
+fn bit(bits: [u64; 2], index: uint) -> uint {
+    if index >= 64 {
+        ((bits[0] >> (index - 64)) & 1) as uint
+    } else {
+        ((bits[1] >> index) & 1) as uint
+    }
+}
+
+/// Errors raised while reading or writing a database.
+#[derive(Clone, Show, PartialEq, Eq)]
+pub enum MmdbError {
+    /// The underlying file failed.
+    Io(IoError),
+    /// The database is malformed.
+    InvalidDatabase(&'static str),
+    /// An IPv6 address has been looked up in an IPv4-only database.
+    Ipv6LookupInIpv4Database,
+    /// An IPv6 network has been inserted in an IPv4-only database.
+    Ipv6NetworkInIpv4Database,
+    /// A value is too large to be encoded.
+    ValueTooLarge,
+    /// The search tree and data section are too large for the record size.
+    DatabaseTooLarge,
+}
+
+impl Error for MmdbError {
+    fn description(&self) -> &str {
+        match *self {
+            MmdbError::Io(ref err) => err.description(),
+            MmdbError::InvalidDatabase(_) => "invalid MaxMind DB database",
+            MmdbError::Ipv6LookupInIpv4Database => "IPv6 address looked up in an IPv4 database",
+            MmdbError::Ipv6NetworkInIpv4Database => "IPv6 network inserted in an IPv4 database",
+            MmdbError::ValueTooLarge => "value too large",
+            MmdbError::DatabaseTooLarge => "database too large for the record size",
+        }
+    }
+}
+
+impl fmt::String for MmdbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MmdbError::Io(ref err) => write!(f, "{}", err),
+            MmdbError::InvalidDatabase(detail) => write!(f, "{}: {}", self.description(), detail),
+            _ => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+impl FromError<IoError> for MmdbError {
+    fn from_error(err: IoError) -> MmdbError {
+        MmdbError::Io(err)
+    }
+}
@@ -0,0 +1,490 @@
+//! Reader of MaxMind DB files.
+use std::collections::BTreeMap;
+use std::io::File;
+use addr::{self, IpAddress, ipv4, ipv6};
+use net::{IpNetwork, Ipv4Network, Ipv6Network, Network};
+use super::decoder::{be, Decoder};
+use super::{bit, MmdbError, Value, METADATA_MARKER};
+
+/// The description of a database, stored at its end.
+#[derive(Clone, Show, PartialEq)]
+pub struct Metadata {
+    pub binary_format_major_version: u16,
+    pub binary_format_minor_version: u16,
+    /// The creation date of the database, as seconds since the epoch.
+    pub build_epoch: u64,
+    /// The kind of records of the database, such as `GeoLite2-City`.
+    pub database_type: String,
+    /// The description of the database, by language code.
+    pub description: BTreeMap<String, String>,
+    /// 4 if the database only holds IPv4 networks, 6 if it holds IPv6 (and maybe IPv4) networks.
+    pub ip_version: u16,
+    /// The language codes for which the records may have localized names.
+    pub languages: Vec<String>,
+    pub node_count: u32,
+    /// The size of the records of the search tree, in bits: 24, 28 or 32.
+    pub record_size: u16,
+}
+
+impl Metadata {
+    fn from_value(value: Value) -> Result<Metadata, MmdbError> {
+        let map = match value {
+            Value::Map(map) => map,
+            _ => return Err(MmdbError::InvalidDatabase("metadata isn't a map")),
+        };
+
+        let languages = match map.get("languages") {
+            Some(&Value::Array(ref array)) => {
+                array.iter().filter_map(|value| value.as_str()).map(|s| s.to_string()).collect()
+            }
+            _ => Vec::new(),
+        };
+        let description = match map.get("description") {
+            Some(&Value::Map(ref descriptions)) => {
+                descriptions.iter()
+                            .filter_map(|(lang, value)| value.as_str().map(|s| (lang.clone(), s)))
+                            .map(|(lang, s)| (lang, s.to_string()))
+                            .collect()
+            }
+            _ => BTreeMap::new(),
+        };
+
+        let metadata = Metadata {
+            binary_format_major_version: try!(field(&map, "binary_format_major_version")) as u16,
+            binary_format_minor_version: try!(field(&map, "binary_format_minor_version")) as u16,
+            build_epoch: try!(field(&map, "build_epoch")),
+            database_type: map.get("database_type").and_then(|value| value.as_str())
+                              .unwrap_or("").to_string(),
+            description: description,
+            ip_version: try!(field(&map, "ip_version")) as u16,
+            languages: languages,
+            node_count: try!(field(&map, "node_count")) as u32,
+            record_size: try!(field(&map, "record_size")) as u16,
+        };
+
+        if metadata.binary_format_major_version != 2 {
+            return Err(MmdbError::InvalidDatabase("unsupported format version"));
+        }
+        if metadata.record_size != 24 && metadata.record_size != 28 && metadata.record_size != 32 {
+            return Err(MmdbError::InvalidDatabase("unsupported record size"));
+        }
+        if metadata.ip_version != 4 && metadata.ip_version != 6 {
+            return Err(MmdbError::InvalidDatabase("invalid IP version"));
+        }
+        Ok(metadata)
+    }
+
+    /// The size of the search tree, in bytes.
+    fn tree_size(&self) -> uint {
+        self.node_count as uint * self.record_size as uint / 4
+    }
+}
+
+/// Get an unsigned integer field of the metadata.
+fn field(map: &BTreeMap<String, Value>, key: &str) -> Result<u64, MmdbError> {
+    match map.get(key).and_then(|value| value.as_u64()) {
+        Some(n) => Ok(n),
+        None => Err(MmdbError::InvalidDatabase("missing or invalid metadata field")),
+    }
+}
+
+/// A MaxMind DB database, loaded in memory.
+pub struct Reader {
+    buf: Vec<u8>,
+    metadata: Metadata,
+    /// The bounds of the data section in `buf`.
+    data: (uint, uint),
+    /// The node reached by the first 96 bits of IPv4 addresses in an IPv6 tree, and its depth.
+    ipv4_start: (u32, uint),
+}
+
+impl Reader {
+    /// Load the database stored in the file at `path`.
+    pub fn open(path: &Path) -> Result<Reader, MmdbError> {
+        let buf = try!(File::open(path).read_to_end());
+        Reader::from_bytes(buf)
+    }
+
+    /// Load a database from its content.
+    pub fn from_bytes(buf: Vec<u8>) -> Result<Reader, MmdbError> {
+        let marker = match rfind(buf.as_slice(), METADATA_MARKER) {
+            Some(marker) => marker,
+            None => return Err(MmdbError::InvalidDatabase("metadata not found")),
+        };
+
+        let (value, _) = try!(Decoder::new(&buf[marker + METADATA_MARKER.len()..]).decode(0));
+        let metadata = try!(Metadata::from_value(value));
+
+        let data_start = metadata.tree_size() + 16;
+        if data_start > marker {
+            return Err(MmdbError::InvalidDatabase("search tree overlaps metadata"));
+        }
+
+        let mut reader = Reader {
+            buf: buf,
+            metadata: metadata,
+            data: (data_start, marker),
+            ipv4_start: (0, 0),
+        };
+
+        if reader.metadata.ip_version == 6 {
+            let (mut node, mut depth) = (0, 0);
+            while depth < 96 && node < reader.metadata.node_count {
+                node = reader.read_node(node, 0);
+                depth += 1;
+            }
+            reader.ipv4_start = (node, depth);
+        }
+        Ok(reader)
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Look up the record of `ip`, returning it with the network it applies to.
+    ///
+    /// IPv4 addresses are looked up in the `::/96` subtree of IPv6 databases, and their networks
+    /// are returned as IPv4 networks.
+    pub fn lookup(&self, ip: addr::IpAddr) -> Result<Option<(IpNetwork, Value)>, MmdbError> {
+        let (bits, (mut node, mut depth)) = match (ip, self.metadata.ip_version) {
+            (addr::Ipv4Addr(ip), 4) => ([0, ip.to_u32() as u64], (0, 96)),
+            (addr::Ipv4Addr(ip), _) => ([0, ip.to_u32() as u64], self.ipv4_start),
+            (addr::Ipv6Addr(_), 4) => return Err(MmdbError::Ipv6LookupInIpv4Database),
+            (addr::Ipv6Addr(ip), _) => (ip.to_u128(), (0, 0)),
+        };
+
+        // Depths are counted in the IPv6 space, IPv4 trees starting at 96.
+        let node_count = self.metadata.node_count;
+        while node < node_count && depth < 128 {
+            node = self.read_node(node, bit(bits, 127 - depth));
+            depth += 1;
+        }
+
+        if node < node_count {
+            return Err(MmdbError::InvalidDatabase("search tree deeper than addresses"));
+        }
+        if node == node_count {
+            return Ok(None);
+        }
+
+        let value = try!(self.resolve(node));
+        Ok(Some((to_network(bits, depth), value)))
+    }
+
+    /// Iterate over the networks of the database and their records.
+    ///
+    /// Networks are yielded in ascending order. The aliases of the IPv4 subtree found in IPv6
+    /// databases (such as `::ffff:0:0/96`) are skipped, and the networks of the subtree are
+    /// yielded as IPv4 networks.
+    pub fn networks(&self) -> Networks {
+        let depth = if self.metadata.ip_version == 4 { 96 } else { 0 };
+        Networks {
+            reader: self,
+            stack: vec![(0, [0, 0], depth)],
+        }
+    }
+
+    /// Read the left (`bit == 0`) or right record of `node`.
+    fn read_node(&self, node: u32, bit: uint) -> u32 {
+        let size = self.metadata.record_size as uint;
+        let offset = node as uint * size / 4;
+        let b = &self.buf[offset..offset + size / 4];
+
+        let record = match (size, bit) {
+            (24, 0) => be(&b[0..3]),
+            (24, _) => be(&b[3..6]),
+            (28, 0) => ((b[3] as u64 & 0xf0) << 20) | be(&b[0..3]),
+            (28, _) => ((b[3] as u64 & 0x0f) << 24) | be(&b[4..7]),
+            (_, 0) => be(&b[0..4]),
+            (_, _) => be(&b[4..8]),
+        };
+        record as u32
+    }
+
+    /// Decode the data pointed to by a record.
+    fn resolve(&self, record: u32) -> Result<Value, MmdbError> {
+        let offset = (record - self.metadata.node_count) as uint;
+        if offset < 16 {
+            return Err(MmdbError::InvalidDatabase("invalid data pointer"));
+        }
+
+        let (start, end) = self.data;
+        let (value, _) = try!(Decoder::new(&self.buf[start..end]).decode(offset - 16));
+        Ok(value)
+    }
+}
+
+/// Iterator over the networks of a database, created by `Reader::networks`.
+///
+/// The iteration stops after the first error.
+pub struct Networks<'a> {
+    reader: &'a Reader,
+    /// The nodes to visit, with the bits leading to them and their depth.
+    stack: Vec<(u32, [u64; 2], uint)>,
+}
+
+impl<'a> Iterator for Networks<'a> {
+    type Item = Result<(IpNetwork, Value), MmdbError>;
+
+    fn next(&mut self) -> Option<Result<(IpNetwork, Value), MmdbError>> {
+        let node_count = self.reader.metadata.node_count;
+        let (ipv4_start, ipv4_depth) = self.reader.ipv4_start;
+
+        while let Some((node, bits, depth)) = self.stack.pop() {
+            if node > node_count {
+                let network = to_network(bits, depth);
+                let result = self.reader.resolve(node).map(|value| (network, value));
+                if result.is_err() {
+                    self.stack.clear();
+                }
+                return Some(result);
+            }
+
+            let alias = self.reader.metadata.ip_version == 6 && ipv4_depth == 96
+                        && node == ipv4_start && (depth, bits) != (96, [0, 0]);
+            if node == node_count || alias {
+                continue;
+            }
+            if depth == 128 {
+                self.stack.clear();
+                return Some(Err(MmdbError::InvalidDatabase("search tree deeper than addresses")));
+            }
+
+            let mut right = bits;
+            set_bit(&mut right, 127 - depth);
+            self.stack.push((self.reader.read_node(node, 1), right, depth + 1));
+            self.stack.push((self.reader.read_node(node, 0), bits, depth + 1));
+        }
+        None
+    }
+}
+
+/// Find the last occurrence of `needle` in `haystack`.
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<uint> {
+    if haystack.len() < needle.len() {
+        return None;
+    }
+
+    range(0, haystack.len() - needle.len() + 1).rev()
+        .find(|&i| &haystack[i..i + needle.len()] == needle)
+}
+
+fn set_bit(bits: &mut [u64; 2], index: uint) {
+    if index >= 64 {
+        bits[0] |= 1 << (index - 64);
+    } else {
+        bits[1] |= 1 << index;
+    }
+}
+
+/// The network of the `depth` first bits of `bits`, in the IPv6 space.
+///
+/// Networks of the IPv4 subtree `::/96` are returned as IPv4 networks.
+fn to_network(bits: [u64; 2], depth: uint) -> IpNetwork {
+    if depth >= 96 && bits[0] == 0 && bits[1] >> 32 == 0 {
+        Ipv4Network(masked(ipv4::IpAddr::from_u32(bits[1] as u32), depth - 96))
+    } else {
+        Ipv6Network(masked(ipv6::IpAddr::from_u128(bits), depth))
+    }
+}
+
+fn masked<A: IpAddress>(addr: A, prefix: uint) -> Network<A> {
+    Network(Network(addr, prefix).address(), prefix)
+}
+
+#[cfg(test)]
+mod test {
+    use addr::{self, ipv4, ipv6};
+    use net::{IpNetwork, Ipv4Network, Ipv6Network, Network};
+    use mmdb::{MmdbError, Value, METADATA_MARKER};
+    use super::Reader;
+
+    enum Child {
+        Empty,
+        Node(uint),
+        Data(uint),
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut bytes = vec![0x40 | s.len() as u8];
+        bytes.push_all(s.as_bytes());
+        bytes
+    }
+
+    fn uint(kind: u8, n: u64) -> Vec<u8> {
+        let len = range(0, 8u).find(|&i| n >> (8 * i) == 0).unwrap_or(8);
+        let mut bytes = match kind {
+            0...7 => vec![kind << 5 | len as u8],
+            _ => vec![len as u8, kind - 7],
+        };
+        bytes.extend(range(0, len).rev().map(|i| (n >> (8 * i)) as u8));
+        bytes
+    }
+
+    fn map(pairs: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = vec![0xe0 | pairs.len() as u8];
+        for &(key, ref value) in pairs.iter() {
+            bytes.push_all(string(key).as_slice());
+            bytes.push_all(value.as_slice());
+        }
+        bytes
+    }
+
+    /// Build a database mapping each network to the data at the given offset of `data`.
+    fn database(ip_version: u16, record_size: u16, networks: &[(IpNetwork, uint)],
+                data: &[u8]) -> Vec<u8> {
+        let mut nodes = vec![[Child::Empty, Child::Empty]];
+        for &(net, offset) in networks.iter() {
+            let (bits, prefix) = match (net, ip_version) {
+                (Ipv4Network(net), 4) => ([0, net.address().to_u32() as u64], net.prefix()),
+                (Ipv4Network(net), _) => ([0, net.address().to_u32() as u64], net.prefix() + 96),
+                (Ipv6Network(net), _) => (net.address().to_u128(), net.prefix()),
+            };
+            let width = if ip_version == 4 { 32 } else { 128 };
+
+            let mut node = 0;
+            for depth in range(0, prefix) {
+                let index = width - 1 - depth;
+                let bit = if index >= 64 { bits[0] >> (index - 64) } else { bits[1] >> index } & 1;
+                if depth == prefix - 1 {
+                    nodes[node][bit as uint] = Child::Data(offset);
+                    break;
+                }
+                node = match nodes[node][bit as uint] {
+                    Child::Node(next) => next,
+                    _ => {
+                        nodes.push([Child::Empty, Child::Empty]);
+                        nodes[node][bit as uint] = Child::Node(nodes.len() - 1);
+                        nodes.len() - 1
+                    }
+                };
+            }
+        }
+
+        let node_count = nodes.len() as u64;
+        let record = |child: &Child| match *child {
+            Child::Empty => node_count,
+            Child::Node(node) => node as u64,
+            Child::Data(offset) => node_count + 16 + offset as u64,
+        };
+        let mut bytes = Vec::new();
+        for node in nodes.iter() {
+            let (left, right) = (record(&node[0]), record(&node[1]));
+            match record_size {
+                24 => for &n in [left, right].iter() {
+                    bytes.extend(range(0, 3u).rev().map(|i| (n >> (8 * i)) as u8));
+                },
+                28 => {
+                    bytes.extend(range(0, 3u).rev().map(|i| (left >> (8 * i)) as u8));
+                    bytes.push((((left >> 24) & 0xf) << 4 | ((right >> 24) & 0xf)) as u8);
+                    bytes.extend(range(0, 3u).rev().map(|i| (right >> (8 * i)) as u8));
+                }
+                _ => for &n in [left, right].iter() {
+                    bytes.extend(range(0, 4u).rev().map(|i| (n >> (8 * i)) as u8));
+                },
+            }
+        }
+
+        bytes.push_all(&[0; 16]);
+        bytes.push_all(data);
+        bytes.push_all(METADATA_MARKER);
+        bytes.push_all(map(&[
+            ("binary_format_major_version", uint(5, 2)),
+            ("binary_format_minor_version", uint(5, 0)),
+            ("build_epoch", uint(9, 1700000000)),
+            ("database_type", string("Test")),
+            ("description", map(&[("en", string("Test database"))])),
+            ("ip_version", uint(5, ip_version as u64)),
+            ("languages", vec![0x01, 0x04, 0x42, b'e', b'n']),
+            ("node_count", uint(6, node_count)),
+            ("record_size", uint(5, record_size as u64)),
+        ]).as_slice());
+        bytes
+    }
+
+    /// Two records, and their offsets in the data section.
+    fn data() -> (Vec<u8>, uint, uint) {
+        let mut data = map(&[("country", string("FR"))]);
+        let offset = data.len();
+        data.push_all(map(&[("asn", uint(6, 64496))]).as_slice());
+        (data, 0, offset)
+    }
+
+    fn ipv4_net(a: u8, b: u8, c: u8, d: u8, prefix: uint) -> IpNetwork {
+        Ipv4Network(Network(ipv4::IpAddr(a, b, c, d), prefix))
+    }
+
+    fn ipv6_net(a: u16, b: u16, prefix: uint) -> IpNetwork {
+        Ipv6Network(Network(ipv6::IpAddr(a, b, 0, 0, 0, 0, 0, 0), prefix))
+    }
+
+    #[test]
+    fn test_ipv4() {
+        let (data, fr, asn) = data();
+        let networks = [(ipv4_net(10, 0, 0, 0, 8), fr), (ipv4_net(192, 0, 2, 0, 24), asn)];
+        let reader = Reader::from_bytes(database(4, 24, &networks, data.as_slice())).unwrap();
+
+        let metadata = reader.metadata();
+        assert_eq!(metadata.database_type.as_slice(), "Test");
+        assert_eq!(metadata.languages, vec!["en".to_string()]);
+        assert_eq!(metadata.description.get("en").map(|s| s.as_slice()), Some("Test database"));
+        assert_eq!(metadata.build_epoch, 1700000000);
+
+        let ip = addr::Ipv4Addr(ipv4::IpAddr(10, 1, 2, 3));
+        let (net, value) = reader.lookup(ip).unwrap().unwrap();
+        assert_eq!(net, ipv4_net(10, 0, 0, 0, 8));
+        assert_eq!(value.get("country").and_then(|v| v.as_str()), Some("FR"));
+
+        let ip = addr::Ipv4Addr(ipv4::IpAddr(192, 0, 2, 200));
+        let (net, value) = reader.lookup(ip).unwrap().unwrap();
+        assert_eq!(net, ipv4_net(192, 0, 2, 0, 24));
+        assert_eq!(value.get("asn"), Some(&Value::Uint32(64496)));
+
+        assert_eq!(reader.lookup(addr::Ipv4Addr(ipv4::IpAddr(8, 8, 8, 8))), Ok(None));
+        assert_eq!(reader.lookup(addr::Ipv6Addr(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+                   Err(MmdbError::Ipv6LookupInIpv4Database));
+
+        let networks: Vec<IpNetwork> = reader.networks().map(|r| r.unwrap().0).collect();
+        assert_eq!(networks, vec![ipv4_net(10, 0, 0, 0, 8), ipv4_net(192, 0, 2, 0, 24)]);
+    }
+
+    #[test]
+    fn test_ipv6() {
+        let (data, fr, asn) = data();
+        let networks = [(ipv6_net(0x2001, 0xdb8, 32), fr), (ipv4_net(192, 0, 2, 0, 24), asn)];
+
+        for &record_size in [24, 28, 32].iter() {
+            let bytes = database(6, record_size, &networks, data.as_slice());
+            let reader = Reader::from_bytes(bytes).unwrap();
+            assert_eq!(reader.metadata().record_size, record_size);
+
+            let ip = addr::Ipv6Addr(ipv6::IpAddr(0x2001, 0xdb8, 0xffff, 0, 0, 0, 0, 1));
+            let (net, value) = reader.lookup(ip).unwrap().unwrap();
+            assert_eq!(net, ipv6_net(0x2001, 0xdb8, 32));
+            assert_eq!(value.get("country").and_then(|v| v.as_str()), Some("FR"));
+
+            let ip = addr::Ipv4Addr(ipv4::IpAddr(192, 0, 2, 1));
+            let (net, _) = reader.lookup(ip).unwrap().unwrap();
+            assert_eq!(net, ipv4_net(192, 0, 2, 0, 24));
+
+            assert_eq!(reader.lookup(addr::Ipv4Addr(ipv4::IpAddr(198, 51, 100, 1))), Ok(None));
+            assert_eq!(reader.lookup(addr::Ipv6Addr(ipv6::IpAddr(0x2001, 0xdb9, 0, 0, 0, 0, 0, 1))),
+                       Ok(None));
+
+            let networks: Vec<IpNetwork> = reader.networks().map(|r| r.unwrap().0).collect();
+            assert_eq!(networks, vec![ipv4_net(192, 0, 2, 0, 24), ipv6_net(0x2001, 0xdb8, 32)]);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Reader::from_bytes(vec![0; 64]).is_err());
+
+        let (data, fr, _) = data();
+        let mut bytes = database(4, 24, &[(ipv4_net(10, 0, 0, 0, 8), fr)], data.as_slice());
+        // Truncate the metadata.
+        bytes.pop();
+        assert!(Reader::from_bytes(bytes).is_err());
+    }
+}
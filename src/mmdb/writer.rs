@@ -0,0 +1,336 @@
+//! Writer of MaxMind DB files.
+use std::collections::{BTreeMap, HashMap};
+use addr::{IpAddrVersion, Ipv4, Ipv6};
+use net::{IpNetwork, Ipv4Network, Ipv6Network};
+use super::encoder::{encode, push_uint};
+use super::{bit, MmdbError, Value, METADATA_MARKER};
+
+/// The size of the records of the search tree, which bounds the size of the database.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub enum RecordSize {
+    Bits24,
+    Bits28,
+    Bits32,
+}
+
+impl RecordSize {
+    pub fn bits(&self) -> uint {
+        match *self {
+            RecordSize::Bits24 => 24,
+            RecordSize::Bits28 => 28,
+            RecordSize::Bits32 => 32,
+        }
+    }
+}
+
+/// A record of the search tree being built.
+#[derive(Copy, Clone, Show, PartialEq, Eq)]
+enum Record {
+    Empty,
+    Node(uint),
+    /// The offset of the data in the data section.
+    Data(uint),
+}
+
+/// The search tree, walked with bits counted in the IPv6 space.
+#[derive(Clone)]
+struct Tree {
+    nodes: Vec<[Record; 2]>,
+}
+
+impl Tree {
+    /// Set the record of the network of the `prefix` first bits of `bits`, the tree starting at
+    /// depth `start`.
+    fn set(&mut self, bits: [u64; 2], start: uint, prefix: uint, record: Record) {
+        if prefix == start {
+            self.nodes[0] = [record, record];
+            return;
+        }
+
+        let node = self.node_at(bits, start, prefix - 1);
+        self.nodes[node][bit(bits, 127 - (prefix - 1))] = record;
+    }
+
+    /// Get the node of the network of the `prefix` first bits of `bits`, creating it if needed.
+    fn node_at(&mut self, bits: [u64; 2], start: uint, prefix: uint) -> uint {
+        let mut node = 0;
+        for depth in range(start, prefix) {
+            let b = bit(bits, 127 - depth);
+            node = match self.nodes[node][b] {
+                Record::Node(child) => child,
+                record => {
+                    // The record keeps applying to the rest of the new node.
+                    self.nodes.push([record, record]);
+                    self.nodes[node][b] = Record::Node(self.nodes.len() - 1);
+                    self.nodes.len() - 1
+                }
+            };
+        }
+        node
+    }
+}
+
+/// A MaxMind DB database being built.
+///
+/// Networks are mapped to records with `insert`, and the database is then serialized with
+/// `to_bytes`. Identical records are only stored once.
+pub struct Writer {
+    /// The kind of records of the database, such as `GeoLite2-City`.
+    pub database_type: String,
+    /// The language codes for which the records may have localized names.
+    pub languages: Vec<String>,
+    /// The description of the database, by language code.
+    pub description: BTreeMap<String, String>,
+    /// The creation date of the database, as seconds since the epoch.
+    pub build_epoch: u64,
+    /// Whether the IPv4 networks of IPv6 databases are also reachable through their IPv4-mapped
+    /// (`::ffff:0:0/96`) and 6to4 (`2002::/16`) addresses. Enabled by default.
+    pub ipv4_aliases: bool,
+    version: IpAddrVersion,
+    record_size: RecordSize,
+    tree: Tree,
+    data: Vec<u8>,
+    /// The offset of each encoded record in the data section.
+    offsets: HashMap<Vec<u8>, uint>,
+}
+
+impl Writer {
+    /// Create an empty database of IPv4 or IPv6 (and IPv4) networks.
+    pub fn new(database_type: &str, version: IpAddrVersion, record_size: RecordSize) -> Writer {
+        Writer {
+            database_type: database_type.to_string(),
+            languages: Vec::new(),
+            description: BTreeMap::new(),
+            build_epoch: 0,
+            ipv4_aliases: true,
+            version: version,
+            record_size: record_size,
+            tree: Tree {
+                nodes: vec![[Record::Empty, Record::Empty]],
+            },
+            data: Vec::new(),
+            offsets: HashMap::new(),
+        }
+    }
+
+    /// Map `net` to `value`, replacing the records of the networks it contains.
+    ///
+    /// IPv4 networks are stored in the `::/96` subtree of IPv6 databases.
+    pub fn insert(&mut self, net: IpNetwork, value: &Value) -> Result<(), MmdbError> {
+        let (bits, prefix) = match (net, self.version) {
+            (Ipv4Network(net), _) => ([0, net.address().to_u32() as u64], net.prefix() + 96),
+            (Ipv6Network(_), Ipv4) => return Err(MmdbError::Ipv6NetworkInIpv4Database),
+            (Ipv6Network(net), Ipv6) => (net.address().to_u128(), net.prefix()),
+        };
+
+        let mut encoded = Vec::new();
+        try!(encode(value, &mut encoded));
+        let existing = self.offsets.get(&encoded).map(|&offset| offset);
+        let offset = match existing {
+            Some(offset) => offset,
+            None => {
+                let offset = self.data.len();
+                self.data.push_all(encoded.as_slice());
+                self.offsets.insert(encoded, offset);
+                offset
+            }
+        };
+
+        self.tree.set(bits, self.start(), prefix, Record::Data(offset));
+        Ok(())
+    }
+
+    /// Serialize the database.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MmdbError> {
+        let mut tree = self.tree.clone();
+        if self.ipv4_aliases && self.version == Ipv6 {
+            let ipv4 = tree.node_at([0, 0], 0, 96);
+            tree.set([0, 0xffff << 32], 0, 96, Record::Node(ipv4));
+            tree.set([0x2002 << 48, 0], 0, 16, Record::Node(ipv4));
+        }
+
+        // Number the nodes reachable from the root, breadth first.
+        let mut numbers = Vec::from_elem(tree.nodes.len(), None);
+        let mut order = vec![0];
+        numbers[0] = Some(0);
+        let mut i = 0;
+        while i < order.len() {
+            for record in tree.nodes[order[i]].iter() {
+                if let Record::Node(child) = *record {
+                    if numbers[child].is_none() {
+                        numbers[child] = Some(order.len());
+                        order.push(child);
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        let node_count = order.len();
+        let bits = self.record_size.bits();
+        if (node_count + 16 + self.data.len()) as u64 >= 1 << bits {
+            return Err(MmdbError::DatabaseTooLarge);
+        }
+
+        let value = |record: Record| match record {
+            Record::Empty => node_count as u64,
+            Record::Node(child) => numbers[child].unwrap() as u64,
+            Record::Data(offset) => (node_count + 16 + offset) as u64,
+        };
+        let mut buf = Vec::with_capacity(node_count * bits / 4 + 16 + self.data.len());
+        for &node in order.iter() {
+            let (left, right) = (value(tree.nodes[node][0]), value(tree.nodes[node][1]));
+            match self.record_size {
+                RecordSize::Bits24 => {
+                    push_uint(left, 3, &mut buf);
+                    push_uint(right, 3, &mut buf);
+                }
+                RecordSize::Bits28 => {
+                    push_uint(left, 3, &mut buf);
+                    buf.push((((left >> 24) & 0xf) << 4 | ((right >> 24) & 0xf)) as u8);
+                    push_uint(right, 3, &mut buf);
+                }
+                RecordSize::Bits32 => {
+                    push_uint(left, 4, &mut buf);
+                    push_uint(right, 4, &mut buf);
+                }
+            }
+        }
+
+        buf.push_all(&[0; 16]);
+        buf.push_all(self.data.as_slice());
+        buf.push_all(METADATA_MARKER);
+        try!(encode(&self.metadata(node_count), &mut buf));
+        Ok(buf)
+    }
+
+    fn metadata(&self, node_count: uint) -> Value {
+        let ip_version = match self.version {
+            Ipv4 => 4,
+            Ipv6 => 6,
+        };
+        let languages = self.languages.iter().map(|lang| Value::String(lang.clone())).collect();
+        let description = self.description.iter()
+                                          .map(|(lang, s)| (lang.clone(), Value::String(s.clone())))
+                                          .collect();
+
+        let fields = vec![
+            ("binary_format_major_version", Value::Uint16(2)),
+            ("binary_format_minor_version", Value::Uint16(0)),
+            ("build_epoch", Value::Uint64(self.build_epoch)),
+            ("database_type", Value::String(self.database_type.clone())),
+            ("description", Value::Map(description)),
+            ("ip_version", Value::Uint16(ip_version)),
+            ("languages", Value::Array(languages)),
+            ("node_count", Value::Uint32(node_count as u32)),
+            ("record_size", Value::Uint16(self.record_size.bits() as u16)),
+        ];
+        Value::Map(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    /// The depth of the root of the tree: IPv4 trees are walked from the 96th bit.
+    fn start(&self) -> uint {
+        match self.version {
+            Ipv4 => 96,
+            Ipv6 => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use addr::{self, Ipv4, Ipv6, ipv4, ipv6};
+    use net::{IpNetwork, Ipv4Network, Ipv6Network, Network};
+    use mmdb::{MmdbError, Reader, Value};
+    use super::{RecordSize, Writer};
+
+    fn record(key: &str, value: &str) -> Value {
+        let mut map = BTreeMap::new();
+        map.insert(key.to_string(), Value::String(value.to_string()));
+        Value::Map(map)
+    }
+
+    fn ipv4_net(a: u8, b: u8, c: u8, d: u8, prefix: uint) -> IpNetwork {
+        Ipv4Network(Network(ipv4::IpAddr(a, b, c, d), prefix))
+    }
+
+    fn ipv6_net(ip: ipv6::IpAddr, prefix: uint) -> IpNetwork {
+        Ipv6Network(Network(ip, prefix))
+    }
+
+    #[test]
+    fn test_ipv6() {
+        for &record_size in [RecordSize::Bits24, RecordSize::Bits28, RecordSize::Bits32].iter() {
+            let mut writer = Writer::new("Test", Ipv6, record_size);
+            writer.languages.push("en".to_string());
+            writer.description.insert("en".to_string(), "Test database".to_string());
+            writer.build_epoch = 1700000000;
+
+            let doc = ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0);
+            writer.insert(ipv6_net(doc, 32), &record("country", "FR")).unwrap();
+            writer.insert(ipv4_net(192, 0, 2, 0, 24), &record("country", "DE")).unwrap();
+            let len = writer.data.len();
+            writer.insert(ipv4_net(198, 51, 100, 0, 24), &record("country", "FR")).unwrap();
+            assert_eq!(writer.data.len(), len);
+
+            let reader = Reader::from_bytes(writer.to_bytes().unwrap()).unwrap();
+            let metadata = reader.metadata();
+            assert_eq!(metadata.record_size as uint, record_size.bits());
+            assert_eq!(metadata.ip_version, 6);
+            assert_eq!(metadata.database_type.as_slice(), "Test");
+            assert_eq!(metadata.languages, vec!["en".to_string()]);
+            assert_eq!(metadata.build_epoch, 1700000000);
+
+            let ip = addr::Ipv4Addr(ipv4::IpAddr(192, 0, 2, 1));
+            let expected = (ipv4_net(192, 0, 2, 0, 24), record("country", "DE"));
+            assert_eq!(reader.lookup(ip), Ok(Some(expected)));
+
+            let mapped = ipv6::IpAddr(0, 0, 0, 0, 0, 0xffff, 0xc000, 0x201);
+            let (net, value) = reader.lookup(addr::Ipv6Addr(mapped)).unwrap().unwrap();
+            assert_eq!(net, ipv6_net(ipv6::IpAddr(0, 0, 0, 0, 0, 0xffff, 0xc000, 0x200), 120));
+            assert_eq!(value, record("country", "DE"));
+
+            let six_to_four = ipv6::IpAddr(0x2002, 0xc633, 0x6401, 0, 0, 0, 0, 1);
+            let (net, value) = reader.lookup(addr::Ipv6Addr(six_to_four)).unwrap().unwrap();
+            assert_eq!(net, ipv6_net(ipv6::IpAddr(0x2002, 0xc633, 0x6400, 0, 0, 0, 0, 0), 40));
+            assert_eq!(value, record("country", "FR"));
+
+            let networks: Vec<IpNetwork> = reader.networks().map(|r| r.unwrap().0).collect();
+            assert_eq!(networks, vec![ipv4_net(192, 0, 2, 0, 24), ipv4_net(198, 51, 100, 0, 24),
+                                      ipv6_net(doc, 32)]);
+        }
+    }
+
+    #[test]
+    fn test_ipv4() {
+        let mut writer = Writer::new("Test", Ipv4, RecordSize::Bits24);
+        writer.insert(ipv4_net(10, 0, 0, 0, 8), &record("site", "a")).unwrap();
+        writer.insert(ipv4_net(10, 1, 0, 0, 16), &record("site", "b")).unwrap();
+        let doc = ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0);
+        assert_eq!(writer.insert(ipv6_net(doc, 32), &record("site", "c")),
+                   Err(MmdbError::Ipv6NetworkInIpv4Database));
+
+        let reader = Reader::from_bytes(writer.to_bytes().unwrap()).unwrap();
+        let lookup = |a: u8, b: u8, c: u8, d: u8| {
+            reader.lookup(addr::Ipv4Addr(ipv4::IpAddr(a, b, c, d))).unwrap()
+        };
+        assert_eq!(lookup(10, 1, 2, 3), Some((ipv4_net(10, 1, 0, 0, 16), record("site", "b"))));
+        assert_eq!(lookup(10, 200, 0, 1), Some((ipv4_net(10, 128, 0, 0, 9), record("site", "a"))));
+        assert_eq!(lookup(11, 0, 0, 1), None);
+
+        // Networks replace the networks they contain.
+        writer.insert(ipv4_net(10, 0, 0, 0, 8), &record("site", "c")).unwrap();
+        let reader = Reader::from_bytes(writer.to_bytes().unwrap()).unwrap();
+        let networks: Vec<(IpNetwork, Value)> = reader.networks().map(|r| r.unwrap()).collect();
+        assert_eq!(networks, vec![(ipv4_net(10, 0, 0, 0, 8), record("site", "c"))]);
+    }
+
+    #[test]
+    fn test_too_large() {
+        let mut writer = Writer::new("Test", Ipv6, RecordSize::Bits24);
+        let big = Value::Bytes(Vec::from_elem(1 << 24, 0));
+        writer.insert(ipv4_net(10, 0, 0, 0, 8), &big).unwrap();
+        assert_eq!(writer.to_bytes(), Err(MmdbError::DatabaseTooLarge));
+    }
+}
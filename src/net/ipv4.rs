@@ -1,147 +1,44 @@
 //! Provide operations over IPv4 networks.
-use std::cmp::Ordering;
-use std::fmt;
-use addr::ipv4::{IpAddr, MAX_PREFIXLEN};
-use addr::{IpAddrVersion, Ipv4};
-
-#[derive(Copy, Clone, Show, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
-pub struct IpNetwork(pub IpAddr, pub uint);
-
-impl IpNetwork {
-    /// Get the corresponding IP address version.
-    pub fn version(&self) -> IpAddrVersion {
-        Ipv4
-    }
-
-    /// Get the network address for the network.
-    pub fn address(&self) -> IpAddr {
-        let &IpNetwork(addr, _) = self;
-        addr & self.mask()
-    }
-
-    /// Get the broadcast address for the network.
-    pub fn broadcast_address(&self) -> IpAddr {
-        self.address() | !self.mask()
-    }
-
-    /// Get the length of the network prefix, in bits.
-    pub fn prefix(&self) -> uint {
-        let &IpNetwork(_, prefix) = self;
-        prefix
-    }
-
-    /// Get the length of the host prefix, in bits.
-    pub fn host_prefix(&self) -> uint {
-        MAX_PREFIXLEN - self.prefix()
-    }
-
-    /// The total number of addresses in the network.
-    pub fn num_addresses(&self) -> uint {
-        use std::num::Int;
-
-        2u.pow(self.host_prefix())
-    }
-
-    /// Get the mask of the network.
-    pub fn mask(&self) -> IpAddr {
-        IpAddr::with_prefixlen(self.prefix())
-    }
-
-    /// Get the hosts range this network have.
-    pub fn range(&self) -> (IpAddr, IpAddr) {
-        (self.address(), self.broadcast_address())
-    }
-
-    /// `true` if this ip is contained in the network.
-    pub fn contains(&self, ip: IpAddr) -> bool {
-        let (start, stop) = self.range();
-        start <= ip && ip <= stop
-    }
-
-    /// `true` if this network is partly or wholly contained in other or other is wholly contained in this network.
-    pub fn overlaps(&self, other: IpNetwork) -> bool {
-        other.contains(self.address()) || other.contains(self.broadcast_address())
-        || self.contains(other.address()) || self.contains(other.broadcast_address())
-    }
-
-    /// Iterate over all addresses of this network.
-    pub fn iter(&self) -> Hosts {
-        let (start, stop) = self.range();
-        Hosts {
-            state: start,
-            stop: stop,
-        }
-    }
-
-    /// Iterate over all usable hosts of this network.
-    pub fn hosts_iter(&self) -> Hosts {
-        let (start, stop) = self.range();
-        Hosts {
-            state: start + 1,
-            stop: stop - 1,
-        }
-    }
-}
-
-impl PartialOrd for IpNetwork {
-    fn partial_cmp(&self, other: &IpNetwork) -> Option<Ordering> {
-        self.address().partial_cmp(&other.address())
-    }
-}
-
-impl Ord for IpNetwork {
-    fn cmp(&self, other: &IpNetwork) -> Ordering {
-        self.address().cmp(&other.address())
-    }
-}
-
-impl fmt::String for IpNetwork {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}/{}", self.address(), self.prefix())
-    }
+use addr::ipv4::IpAddr;
+use super::network::{self, Network};
+
+pub type IpNetwork = Network<IpAddr>;
+pub type Hosts = network::Hosts<IpAddr>;
+pub type Shuffle = network::Shuffle<IpAddr>;
+
+/// Create an IPv4 network from an address and a prefix length.
+///
+/// `IpNetwork` used to be a tuple struct: this function keeps its constructor working. It
+/// can't be used in patterns, which must now match `Network(ip, prefix)`.
+#[allow(non_snake_case)]
+pub fn IpNetwork(ip: IpAddr, prefix: uint) -> IpNetwork {
+    Network(ip, prefix)
 }
 
-#[derive(Copy, Clone)]
-pub struct Hosts {
-    state: IpAddr,
-    stop: IpAddr,
-}
-
-impl Iterator for Hosts {
-    type Item = IpAddr;
-
-    fn next(&mut self) -> Option<IpAddr> {
-        if self.state <= self.stop {
-            let result = self.state;
-            self.state = self.state + 1;
-            Some(result)
-        } else {
-            None
-        }
+impl Network<IpAddr> {
+    /// The total number of addresses in the network.
+    pub fn num_addresses(&self) -> u64 {
+        1 << self.host_prefix()
     }
 }
 
-impl DoubleEndedIterator for Hosts {
-    fn next_back(&mut self) -> Option<IpAddr> {
-        if self.stop >= self.state {
-            self.stop = self.stop - 1;
-            Some(self.stop)
-        } else {
-            None
-        }
-    }
-}
+/// The number of addresses of an IPv4 network only fits a `uint` on 64-bit targets: a `/0` has
+/// one more than `uint::MAX` on 32-bit ones.
+#[cfg(target_pointer_width = "64")]
+impl ExactSizeIterator for Hosts {}
 
 #[cfg(test)]
 mod test {
-    use super::IpNetwork;
+    use net::Network;
     use addr::ipv4::IpAddr;
+    use super::IpNetwork;
 
     #[test]
     fn test_mask() {
         let net = IpNetwork(IpAddr(127, 0, 0, 1), 24);
 
         assert_eq!(net.mask(), IpAddr(255, 255, 255, 0));
+        assert_eq!(net.hostmask(), IpAddr(0, 0, 0, 255));
     }
 
     #[test]
@@ -154,18 +51,75 @@ mod test {
     #[test]
     fn test_num_addresses() {
         assert_eq!(IpNetwork(IpAddr(127, 0, 0, 1), 24).num_addresses(), 256);
-        assert_eq!(IpNetwork(IpAddr(127, 0, 0, 1), 0).num_addresses(), ::std::u32::MAX as uint + 1);
+        assert_eq!(IpNetwork(IpAddr(127, 0, 0, 1), 0).num_addresses(), ::std::u32::MAX as u64 + 1);
     }
 
     #[test]
     fn test_iter() {
         let net = IpNetwork(IpAddr(127, 0, 0, 1), 24);
 
-        assert_eq!(net.iter().count(), net.num_addresses());
-        assert_eq!(net.iter().rev().count(), net.num_addresses());
+        assert_eq!(net.iter().count() as u64, net.num_addresses());
+        assert_eq!(net.iter().rev().count() as u64, net.num_addresses());
+
+        assert_eq!(net.hosts_iter().count() as u64, net.num_addresses() - 2);
+        assert_eq!(net.hosts_iter().rev().count() as u64, net.num_addresses() - 2);
 
-        assert_eq!(net.hosts_iter().count(), net.num_addresses() - 2);
-        assert_eq!(net.hosts_iter().rev().count(), net.num_addresses() - 2);
+        assert_eq!(net.iter().size_hint(), (256, Some(256)));
+        assert_eq!(net.hosts_iter().size_hint(), (254, Some(254)));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_len() {
+        assert_eq!(IpNetwork(IpAddr(127, 0, 0, 1), 24).iter().len(), 256);
+        assert_eq!(IpNetwork(IpAddr(0, 0, 0, 0), 0).iter().len(), 1 << 32);
+        assert_eq!(IpNetwork(IpAddr(0, 0, 0, 0), 0).hosts_iter().len(), (1 << 32) - 2);
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let net = Network(IpAddr(127, 0, 0, 1), 30);
+
+        let hosts: Vec<IpAddr> = net.iter().rev().collect();
+        assert_eq!(hosts, vec![IpAddr(127, 0, 0, 3), IpAddr(127, 0, 0, 2),
+                               IpAddr(127, 0, 0, 1), IpAddr(127, 0, 0, 0)]);
+
+        let hosts: Vec<IpAddr> = net.hosts_iter().rev().collect();
+        assert_eq!(hosts, vec![IpAddr(127, 0, 0, 2), IpAddr(127, 0, 0, 1)]);
+    }
+
+    #[test]
+    fn test_hosts_small() {
+        let net = Network(IpAddr(10, 0, 0, 0), 31);
+        let hosts: Vec<IpAddr> = net.hosts_iter().collect();
+        assert_eq!(hosts, vec![IpAddr(10, 0, 0, 0), IpAddr(10, 0, 0, 1)]);
+
+        let net = Network(IpAddr(10, 0, 0, 7), 32);
+        let hosts: Vec<IpAddr> = net.hosts_iter().collect();
+        assert_eq!(hosts, vec![IpAddr(10, 0, 0, 7)]);
+
+        let net = Network(IpAddr(255, 255, 255, 255), 32);
+        assert_eq!(net.iter().count(), 1);
+        assert_eq!(net.iter().rev().count(), 1);
+
+        let net = Network(IpAddr(0, 0, 0, 0), 32);
+        assert_eq!(net.iter().rev().count(), 1);
+    }
+
+    #[test]
+    fn test_nth() {
+        let net = Network(IpAddr(10, 0, 0, 0), 8);
+
+        let mut hosts = net.iter();
+        assert_eq!(hosts.nth(0), Some(IpAddr(10, 0, 0, 0)));
+        assert_eq!(hosts.nth(256), Some(IpAddr(10, 0, 1, 1)));
+        assert_eq!(hosts.size_hint(), (16776958, Some(16776958)));
+        assert_eq!(hosts.nth(16776958), None);
+        assert_eq!(hosts.next(), None);
+
+        let mut hosts = net.hosts_iter().skip(16777213);
+        assert_eq!(hosts.next(), Some(IpAddr(10, 255, 255, 254)));
+        assert_eq!(hosts.next(), None);
     }
 
     #[test]
@@ -186,4 +140,119 @@ mod test {
         assert!(net1.overlaps(net2));
         assert!(!net1.overlaps(net3));
     }
+
+    #[test]
+    fn test_subnet_of() {
+        let net1 = Network(IpAddr(10, 1, 0, 0), 16);
+        let net2 = Network(IpAddr(10, 1, 2, 0), 24);
+        let net3 = Network(IpAddr(10, 2, 0, 0), 16);
+
+        assert!(net2.is_subnet_of(net1));
+        assert!(net1.is_subnet_of(net1));
+        assert!(!net1.is_subnet_of(net2));
+        assert!(!net2.is_subnet_of(net3));
+
+        assert!(net1.is_supernet_of(net2));
+        assert!(!net2.is_supernet_of(net1));
+    }
+
+    #[test]
+    fn test_adjacent() {
+        let net1 = Network(IpAddr(10, 1, 0, 0), 16);
+        let net2 = Network(IpAddr(10, 2, 0, 0), 16);
+        let net3 = Network(IpAddr(10, 3, 0, 0), 24);
+
+        assert!(net1.is_adjacent_to(net2));
+        assert!(net2.is_adjacent_to(net1));
+        assert!(net2.is_adjacent_to(net3));
+        assert!(!net1.is_adjacent_to(net3));
+        assert!(!net1.is_adjacent_to(net1));
+
+        let last = Network(IpAddr(255, 255, 255, 0), 24);
+        assert!(!last.is_adjacent_to(Network(IpAddr(0, 0, 0, 0), 24)));
+    }
+
+    #[test]
+    fn test_intersection() {
+        let net1 = Network(IpAddr(10, 1, 0, 0), 16);
+        let net2 = Network(IpAddr(10, 1, 2, 3), 24);
+        let net3 = Network(IpAddr(10, 2, 0, 0), 16);
+
+        assert_eq!(net1.intersection(net2), Some(Network(IpAddr(10, 1, 2, 0), 24)));
+        assert_eq!(net2.intersection(net1), Some(Network(IpAddr(10, 1, 2, 0), 24)));
+        assert_eq!(net1.intersection(net3), None);
+    }
+
+    #[test]
+    fn test_ord() {
+        use std::cmp::Ordering;
+
+        let net1 = Network(IpAddr(10, 0, 0, 0), 8);
+        let net2 = Network(IpAddr(10, 0, 0, 0), 16);
+        let net3 = Network(IpAddr(10, 0, 0, 1), 16);
+        let net4 = Network(IpAddr(9, 0, 0, 0), 24);
+
+        assert_eq!(net1.compare_networks(&net2), Ordering::Less);
+        assert_eq!(net2.compare_networks(&net3), Ordering::Equal);
+        assert_eq!(net4.compare_networks(&net1), Ordering::Less);
+
+        let mut nets = vec![net3, net2, net1, net4];
+        nets.sort();
+        assert_eq!(nets, vec![net4, net1, net2, net3]);
+    }
+
+    #[test]
+    fn test_step_by() {
+        let net = Network(IpAddr(10, 0, 0, 0), 24);
+
+        let hosts: Vec<IpAddr> = net.step_by(100).collect();
+        assert_eq!(hosts, vec![IpAddr(10, 0, 0, 0), IpAddr(10, 0, 0, 100), IpAddr(10, 0, 0, 200)]);
+        assert_eq!(net.step_by(100).size_hint(), (3, Some(3)));
+        assert_eq!(net.step_by(1).count(), 256);
+    }
+
+    #[test]
+    fn test_shuffle_iter() {
+        let net = Network(IpAddr(10, 1, 2, 0), 22);
+
+        let mut hosts: Vec<IpAddr> = net.shuffle_iter(1234).collect();
+        assert_eq!(hosts.len(), 1024);
+        assert!(hosts.iter().all(|&ip| net.contains(ip)));
+        assert!(hosts != net.iter().collect::<Vec<IpAddr>>());
+        assert_eq!(hosts, net.shuffle_iter(1234).collect::<Vec<IpAddr>>());
+
+        hosts.sort();
+        assert_eq!(hosts, net.iter().collect::<Vec<IpAddr>>());
+
+        let net = Network(IpAddr(10, 1, 2, 3), 32);
+        assert_eq!(net.shuffle_iter(0).collect::<Vec<IpAddr>>(), vec![IpAddr(10, 1, 2, 3)]);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_random() {
+        use rand::{SeedableRng, XorShiftRng};
+
+        let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+        let net = Network(IpAddr(10, 1, 2, 0), 30);
+
+        for _ in range(0u, 100) {
+            assert!(net.contains(net.random_address(&mut rng)));
+
+            let ip = net.random_host(&mut rng);
+            assert!(ip == IpAddr(10, 1, 2, 1) || ip == IpAddr(10, 1, 2, 2));
+        }
+
+        let net = Network(IpAddr(10, 1, 2, 0), 31);
+        assert!(net.contains(net.random_host(&mut rng)));
+
+        let net = Network(IpAddr(10, 0, 0, 0), 8);
+        let subnet = net.random_subnet(24, &mut rng).unwrap();
+        assert_eq!(subnet.prefix(), 24);
+        let Network(ip, _) = subnet;
+        assert_eq!(subnet.address(), ip);
+        assert!(net.contains(subnet.address()));
+        assert!(net.random_subnet(4, &mut rng).is_none());
+        assert!(net.random_subnet(33, &mut rng).is_none());
+    }
 }
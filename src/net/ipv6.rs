@@ -1,133 +1,464 @@
 //! Provide operations over IPv6 networks.
-use std::cmp::Ordering;
-use std::fmt;
-use addr::ipv6::{IpAddr, MAX_PREFIXLEN};
-use addr::{IpAddrVersion, Ipv6};
+use addr::ipv4;
+use addr::ipv6::{IpAddr, MulticastScope, MAX_PREFIXLEN};
+use eui::Eui48;
+use sha256::Sha256;
+use super::network::{self, Network};
+#[cfg(feature = "rand")]
+use rand::Rng;
 
-#[derive(Copy, Clone, Show, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
-pub struct IpNetwork(pub IpAddr, pub uint);
+pub type IpNetwork = Network<IpAddr>;
+pub type Hosts = network::Hosts<IpAddr>;
+pub type Shuffle = network::Shuffle<IpAddr>;
 
-impl IpNetwork {
-    /// Get the corresponding IP address version.
-    pub fn version(&self) -> IpAddrVersion {
-        Ipv6
-    }
+/// Create an IPv6 network from an address and a prefix length.
+///
+/// `IpNetwork` used to be a tuple struct: this function keeps its constructor working. It
+/// can't be used in patterns, which must now match `Network(ip, prefix)`.
+#[allow(non_snake_case)]
+pub fn IpNetwork(ip: IpAddr, prefix: uint) -> IpNetwork {
+    Network(ip, prefix)
+}
 
-    /// Get the network address for the network.
-    pub fn address(&self) -> IpAddr {
-        let &IpNetwork(addr, _) = self;
-        addr & self.mask()
-    }
+/// Number of additional attempts made by `stable_privacy_address` when the generated
+/// interface identifier is reserved (RFC 7217).
+pub const IDGEN_RETRIES: u8 = 3;
 
-    /// Get the broadcast address for the network.
-    pub fn broadcast_address(&self) -> IpAddr {
-        self.address() | !self.mask()
+/// The link-local unicast prefix `fe80::/64` (RFC 4291).
+pub const LINK_LOCAL_PREFIX: IpNetwork = Network(IpAddr(0xfe80, 0, 0, 0, 0, 0, 0, 0), 64);
+
+/// The NAT64 Well-Known Prefix `64:ff9b::/96` (RFC 6052).
+pub const NAT64_WELL_KNOWN_PREFIX: IpNetwork = Network(IpAddr(0x64, 0xff9b, 0, 0, 0, 0, 0, 0), 96);
+
+impl Network<IpAddr> {
+    /// The total number of addresses in the network, as a 128-bits integer (see `IpAddr::to_u128`).
+    ///
+    /// The 2^128 addresses of a `/0` network can't be represented, so `None` is returned for it.
+    pub fn num_addresses(&self) -> Option<[u64; 2]> {
+        let n = self.host_prefix();
+        if n == MAX_PREFIXLEN {
+            None
+        } else if n >= 64 {
+            Some([1 << (n - 64), 0])
+        } else {
+            Some([0, 1 << n])
+        }
     }
+}
 
-    /// Get the length of the network prefix, in bits.
-    pub fn prefix(&self) -> uint {
-        let &IpNetwork(_, prefix) = self;
-        prefix
+///! Stateless address autoconfiguration (RFC 4862).
+impl Network<IpAddr> {
+    /// Create the address an interface autoconfigures in this `/64` network from its MAC address,
+    /// using the modified EUI-64 interface identifier.
+    ///
+    /// `None` is returned if this network prefix length isn't `64`.
+    pub fn slaac_address(&self, mac: Eui48) -> Option<IpAddr> {
+        if self.prefix() != 64 {
+            return None;
+        }
+
+        let [hi, _] = self.address().to_u128();
+        Some(IpAddr::from_u128([hi, mac.to_modified_eui64().to_u64()]))
     }
+}
 
-    /// Get the length of the host prefix, in bits.
-    pub fn host_prefix(&self) -> uint {
-        MAX_PREFIXLEN - self.prefix()
+///! Privacy addresses, whose interface identifier doesn't reveal the interface MAC address.
+impl Network<IpAddr> {
+    /// Create a stable, semantically opaque address in this network (RFC 7217).
+    ///
+    /// The interface identifier is made of the lowest bits of the SHA-256 digest of the
+    /// network address, `interface` (e.g. its name or index), `network_id` (e.g. a wireless
+    /// network SSID, or nothing), `dad_counter` and `secret_key`. The same parameters always give
+    /// the same address, which changes with the network.
+    ///
+    /// When the identifier is reserved, the DAD counter is incremented (wrapping around) to
+    /// generate another one, up to `IDGEN_RETRIES` times: `None` is returned if all of them are
+    /// reserved.
+    pub fn stable_privacy_address(&self, interface: &[u8], network_id: &[u8],
+                                  dad_counter: u8, secret_key: &[u8]) -> Option<IpAddr> {
+        for retry in range(0, IDGEN_RETRIES + 1) {
+            let mut hasher = Sha256::new();
+            hasher.update(&self.address().packed());
+            hasher.update(interface);
+            hasher.update(network_id);
+            hasher.update(&[dad_counter.wrapping_add(retry)]);
+            hasher.update(secret_key);
+            let digest = hasher.finish();
+
+            let mut rid = [0u8; 16];
+            for (b, d) in rid.iter_mut().zip(digest[16..].iter()) {
+                *b = *d;
+            }
+
+            let ip = self.address() | (IpAddr::from_packed(rid) & !self.mask());
+            if !self.is_reserved_host(ip) {
+                return Some(ip);
+            }
+        }
+
+        None
     }
 
-    /// The total number of addresses in the network.
-    pub fn num_addresses(&self) -> uint {
-        use std::num::Int;
+    /// `true` if `ip` is the Subnet-Router anycast address of this network, or if this
+    /// network is a `/64` and `ip` has a reserved interface identifier.
+    fn is_reserved_host(&self, ip: IpAddr) -> bool {
+        ip == self.address() || (self.prefix() == 64 && ip.has_reserved_interface_identifier())
+    }
+}
 
-        2u.pow(self.host_prefix())
+///! Temporary addresses, available with the `rand` feature.
+#[cfg(feature = "rand")]
+impl Network<IpAddr> {
+    /// Create a temporary address in this network, with a random interface identifier (RFC 4941).
+    ///
+    /// Reserved interface identifiers are never returned.
+    pub fn temporary_address<R: Rng>(&self, rng: &mut R) -> IpAddr {
+        loop {
+            let ip = self.random_address(rng);
+            if !self.is_reserved_host(ip) {
+                return ip;
+            }
+        }
     }
+}
 
-    /// Get the mask of the network.
-    pub fn mask(&self) -> IpAddr {
-        IpAddr::with_prefixlen(self.prefix())
+///! IPv4-embedded IPv6 addresses of NAT64 prefixes (RFC 6052).
+impl Network<IpAddr> {
+    /// `true` if this network prefix length is allowed for a NAT64 prefix: 32, 40, 48, 56, 64 or 96.
+    pub fn is_nat64_prefix(&self) -> bool {
+        match self.prefix() {
+            32 | 40 | 48 | 56 | 64 | 96 => true,
+            _ => false,
+        }
     }
 
-    /// Get the hosts range this network have.
-    pub fn range(&self) -> (IpAddr, IpAddr) {
-        (self.address(), self.broadcast_address())
+    /// Synthesize the IPv6 address embedding `ip` in this NAT64 prefix.
+    ///
+    /// The IPv4 address bits follow the prefix, skipping the reserved "u" octet (bits 64 to 71),
+    /// which is left to zero, as is the suffix. `None` is returned if the prefix length isn't allowed.
+    pub fn nat64_address(&self, ip: ipv4::IpAddr) -> Option<IpAddr> {
+        if !self.is_nat64_prefix() {
+            return None;
+        }
+
+        let mut bytes = self.address().packed();
+        let mut pos = self.prefix() / 8;
+        for &b in ip.packed().iter() {
+            if pos == 8 {
+                pos += 1;
+            }
+            bytes[pos] = b;
+            pos += 1;
+        }
+
+        Some(IpAddr::from_packed(bytes))
     }
 
-    /// `true` if this ip is contained in the network.
-    pub fn contains(&self, ip: IpAddr) -> bool {
-        let (start, stop) = self.range();
-        start <= ip && ip <= stop
+    /// Extract the IPv4 address embedded in `ip` by this NAT64 prefix.
+    ///
+    /// `None` is returned if the prefix length isn't allowed, if `ip` isn't in this network or if
+    /// its "u" octet isn't zero.
+    pub fn nat64_extract(&self, ip: IpAddr) -> Option<ipv4::IpAddr> {
+        if !self.is_nat64_prefix() || !self.contains(ip) {
+            return None;
+        }
+
+        let bytes = ip.packed();
+        if self.prefix() < 96 && bytes[8] != 0 {
+            return None;
+        }
+
+        let mut octets = [0u8; 4];
+        let mut pos = self.prefix() / 8;
+        for b in octets.iter_mut() {
+            if pos == 8 {
+                pos += 1;
+            }
+            *b = bytes[pos];
+            pos += 1;
+        }
+
+        Some(ipv4::IpAddr(octets[0], octets[1], octets[2], octets[3]))
     }
+}
 
-    /// `true` if this network is partly or wholly contained in other or other is wholly contained in this network.
-    pub fn overlaps(&self, other: IpNetwork) -> bool {
-        other.contains(self.address()) || other.contains(self.broadcast_address())
-        || self.contains(other.address()) || self.contains(other.broadcast_address())
+///! Multicast groups derived from unicast prefixes.
+impl Network<IpAddr> {
+    /// Create the unicast-prefix-based multicast group `group_id` of this network (RFC 3306):
+    /// `ff3s:00ll::/32`, followed by the network prefix and the group ID.
+    ///
+    /// `None` is returned if this network prefix is longer than 64 bits.
+    pub fn unicast_prefix_multicast(&self, scope: MulticastScope, group_id: u32) -> Option<IpAddr> {
+        self.prefix_multicast(0x3, 0, scope, group_id)
     }
 
-    /// Iterate over all addresses of this network.
-    pub fn iter(&self) -> Hosts {
-        let (start, stop) = self.range();
-        Hosts {
-            state: start,
-            stop: stop,
+    /// Create the embedded-RP multicast group `group_id` (RFC 3956) whose rendezvous point is the
+    /// network address of this network, with `riid` as its last 4 bits.
+    ///
+    /// `None` is returned if this network prefix is empty or longer than 64 bits, or if `riid`
+    /// doesn't fit in 4 bits.
+    pub fn embedded_rp_multicast(&self, riid: u8, scope: MulticastScope, group_id: u32) -> Option<IpAddr> {
+        if self.prefix() == 0 || riid > 0xf {
+            return None;
         }
+        self.prefix_multicast(0x7, riid, scope, group_id)
     }
 
-    /// Iterate over all usable hosts of this network.
-    pub fn hosts_iter(&self) -> Hosts {
-        let (start, stop) = self.range();
-        Hosts {
-            state: start + 1,
-            stop: stop - 1,
+    fn prefix_multicast(&self, flags: u8, riid: u8, scope: MulticastScope, group_id: u32) -> Option<IpAddr> {
+        if self.prefix() > 64 {
+            return None;
         }
+
+        let [prefix, _] = self.address().to_u128();
+        let header = (0xffu64 << 24) | ((flags as u64) << 20) | ((scope.to_u8() as u64) << 16)
+                     | ((riid as u64) << 8) | self.prefix() as u64;
+        Some(IpAddr::from_u128([(header << 32) | (prefix >> 32), (prefix << 32) | group_id as u64]))
     }
 }
 
-impl PartialOrd for IpNetwork {
-    fn partial_cmp(&self, other: &IpNetwork) -> Option<Ordering> {
-        self.address().partial_cmp(&other.address())
+#[cfg(test)]
+mod test {
+    use net::Network;
+    use addr::ipv6::IpAddr;
+
+    #[test]
+    fn test_mask() {
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 64);
+
+        assert_eq!(net.mask(), IpAddr(0xffff, 0xffff, 0xffff, 0xffff, 0, 0, 0, 0));
+        assert_eq!(net.hostmask(), IpAddr(0, 0, 0, 0, 0xffff, 0xffff, 0xffff, 0xffff));
+        assert_eq!(net.hostmask().to_netmask(), Some(net.mask()));
     }
-}
 
-impl Ord for IpNetwork {
-    fn cmp(&self, other: &IpNetwork) -> Ordering {
-        self.address().cmp(&other.address())
+    #[test]
+    fn test_num_addresses() {
+        let ip = IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        assert_eq!(Network(ip, 128).num_addresses(), Some([0, 1]));
+        assert_eq!(Network(ip, 120).num_addresses(), Some([0, 256]));
+        assert_eq!(Network(ip, 64).num_addresses(), Some([1, 0]));
+        assert_eq!(Network(ip, 48).num_addresses(), Some([1 << 16, 0]));
+        assert_eq!(Network(ip, 1).num_addresses(), Some([1 << 63, 0]));
+        assert_eq!(Network(ip, 0).num_addresses(), None);
     }
-}
 
-impl fmt::String for IpNetwork {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}/{}", self.address(), self.prefix())
+    #[test]
+    fn test_size_hint() {
+        let ip = IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        assert_eq!(Network(ip, 120).iter().size_hint(), (256, Some(256)));
+        assert_eq!(Network(ip, 100).iter().size_hint(), (1 << 28, Some(1 << 28)));
+        assert_eq!(Network(ip, 48).iter().size_hint(), (::std::uint::MAX, None));
     }
-}
 
-#[derive(Copy, Clone)]
-pub struct Hosts {
-    state: IpAddr,
-    stop: IpAddr,
-}
+    #[test]
+    fn test_hosts() {
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 126);
+        let hosts: Vec<IpAddr> = net.hosts_iter().collect();
+        assert_eq!(hosts, vec![IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+                               IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2),
+                               IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 3)]);
 
-impl Iterator for Hosts {
-    type Item = IpAddr;
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 127);
+        assert_eq!(net.hosts_iter().count(), 2);
 
-    fn next(&mut self) -> Option<IpAddr> {
-        if self.state <= self.stop {
-            let result = self.state;
-            self.state = self.state + 1;
-            Some(result)
-        } else {
-            None
+        let net = Network(IpAddr(0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff), 128);
+        assert_eq!(net.hosts_iter().count(), 1);
+        assert_eq!(net.hosts_iter().rev().count(), 1);
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0x1, 0x0), 127);
+
+        let hosts: Vec<IpAddr> = net.iter().rev().collect();
+        assert_eq!(hosts, vec![IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0x1, 0x1),
+                               IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0x1, 0x0)]);
+
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 64);
+        let mut hosts = net.iter();
+        assert_eq!(hosts.next_back(), Some(IpAddr(0x2001, 0xdb8, 0, 0, 0xffff, 0xffff, 0xffff, 0xffff)));
+        assert_eq!(hosts.next_back(), Some(IpAddr(0x2001, 0xdb8, 0, 0, 0xffff, 0xffff, 0xffff, 0xfffe)));
+    }
+
+    #[test]
+    fn test_nth() {
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 48);
+
+        let mut hosts = net.iter();
+        assert_eq!(hosts.nth(0x1_0000_0000_0000), Some(IpAddr(0x2001, 0xdb8, 0, 0, 1, 0, 0, 0)));
+        assert_eq!(hosts.next(), Some(IpAddr(0x2001, 0xdb8, 0, 0, 1, 0, 0, 1)));
+
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 120);
+        let mut hosts = net.hosts_iter().skip(254);
+        assert_eq!(hosts.next(), Some(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0xff)));
+        assert_eq!(hosts.next(), None);
+
+        let mut hosts = net.iter();
+        assert_eq!(hosts.nth(256), None);
+        assert_eq!(hosts.next(), None);
+    }
+
+    #[test]
+    fn test_step_by() {
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 64);
+
+        let mut hosts = net.step_by(1 << 32);
+        assert_eq!(hosts.next(), Some(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0)));
+        assert_eq!(hosts.next(), Some(IpAddr(0x2001, 0xdb8, 0, 0, 0, 1, 0, 0)));
+        assert_eq!(hosts.next(), Some(IpAddr(0x2001, 0xdb8, 0, 0, 0, 2, 0, 0)));
+    }
+
+    #[test]
+    fn test_shuffle_iter() {
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 118);
+
+        let mut hosts: Vec<IpAddr> = net.shuffle_iter(1234).collect();
+        assert_eq!(hosts.len(), 1024);
+        hosts.sort();
+        assert_eq!(hosts, net.iter().collect::<Vec<IpAddr>>());
+
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 48);
+        for ip in net.shuffle_iter(1234).take(1000) {
+            assert!(net.contains(ip));
         }
     }
-}
 
-impl DoubleEndedIterator for Hosts {
-    fn next_back(&mut self) -> Option<IpAddr> {
-        if self.stop >= self.state {
-            self.stop = self.stop - 1;
-            Some(self.stop)
-        } else {
-            None
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_random() {
+        use rand::{SeedableRng, XorShiftRng};
+
+        let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 127);
+        let ip = net.random_host(&mut rng);
+        assert!(net.contains(ip));
+
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 126);
+        for _ in range(0u, 100) {
+            assert!(net.random_host(&mut rng) != net.address());
         }
+
+        let net = Network(IpAddr(0, 0, 0, 0, 0, 0, 0, 0), 0);
+        let a = net.random_address(&mut rng);
+        let b = net.random_address(&mut rng);
+        assert!(a != b);
+
+        let net = Network(IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32);
+        let subnet = net.random_subnet(64, &mut rng).unwrap();
+        assert_eq!(subnet.prefix(), 64);
+        let Network(ip, _) = subnet;
+        assert_eq!(subnet.address(), ip);
+        assert!(net.contains(subnet.address()));
+        assert!(net.random_subnet(129, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_nat64() {
+        use addr::ipv4;
+        use super::{IpNetwork, NAT64_WELL_KNOWN_PREFIX};
+
+        let ip = ipv4::IpAddr(192, 0, 2, 33);
+        let cases = [
+            ("2001:db8::", 32, "2001:db8:c000:221::"),
+            ("2001:db8:100::", 40, "2001:db8:1c0:2:21::"),
+            ("2001:db8:122::", 48, "2001:db8:122:c000:2:2100::"),
+            ("2001:db8:122:300::", 56, "2001:db8:122:3c0:0:221::"),
+            ("2001:db8:122:344::", 64, "2001:db8:122:344:c0:2:2100:0"),
+            ("2001:db8:122:344::", 96, "2001:db8:122:344::c000:221"),
+        ];
+
+        for &(prefix, len, expected) in cases.iter() {
+            let net: IpNetwork = Network(prefix.parse().unwrap(), len);
+            let expected: IpAddr = expected.parse().unwrap();
+
+            assert_eq!(net.nat64_address(ip), Some(expected));
+            assert_eq!(net.nat64_extract(expected), Some(ip));
+        }
+
+        let expected: IpAddr = "64:ff9b::192.0.2.33".parse().unwrap();
+        assert_eq!(NAT64_WELL_KNOWN_PREFIX.nat64_address(ip), Some(expected));
+
+        let net: IpNetwork = Network("2001:db8::".parse().unwrap(), 33);
+        assert_eq!(net.nat64_address(ip), None);
+
+        let net: IpNetwork = Network("2001:db8::".parse().unwrap(), 32);
+        assert_eq!(net.nat64_extract("2001:db8:c000:221:100::".parse().unwrap()), None);
+        assert_eq!(net.nat64_extract("2001:db9:c000:221::".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn test_slaac() {
+        use eui::Eui48;
+        use super::{IpNetwork, LINK_LOCAL_PREFIX};
+
+        let mac = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd);
+        let net: IpNetwork = Network("2001:db8:1:2::".parse().unwrap(), 64);
+        let ip: IpAddr = "2001:db8:1:2:21b:77ff:fe49:54fd".parse().unwrap();
+
+        assert_eq!(net.slaac_address(mac), Some(ip));
+        assert_eq!(ip.to_eui48(), Some(mac));
+        assert_eq!(LINK_LOCAL_PREFIX.slaac_address(mac), Some(IpAddr::link_local(mac)));
+
+        let net: IpNetwork = Network("2001:db8:1::".parse().unwrap(), 48);
+        assert_eq!(net.slaac_address(mac), None);
+    }
+
+    #[test]
+    fn test_stable_privacy_address() {
+        use super::IpNetwork;
+
+        let net: IpNetwork = Network("2001:db8:1:2::".parse().unwrap(), 64);
+        let ip = net.stable_privacy_address(b"eth0", b"home", 0, b"secret").unwrap();
+        assert_eq!(ip, "2001:db8:1:2:cd:c071:69ff:8a4a".parse().unwrap());
+        assert_eq!(net.stable_privacy_address(b"eth0", b"home", 0, b"secret"), Some(ip));
+
+        let ip = net.stable_privacy_address(b"eth0", b"home", 1, b"secret").unwrap();
+        assert_eq!(ip, "2001:db8:1:2:c5ea:e56c:fb3e:1a28".parse().unwrap());
+        let ip = net.stable_privacy_address(b"eth0", b"home", 255, b"secret").unwrap();
+        assert_eq!(ip, "2001:db8:1:2:9938:5028:5b3:a718".parse().unwrap());
+
+        let net: IpNetwork = Network("2001:db8:1:3::".parse().unwrap(), 64);
+        let ip = net.stable_privacy_address(b"eth0", b"home", 0, b"secret").unwrap();
+        assert_eq!(ip, "2001:db8:1:3:6e49:7c38:804c:4e24".parse().unwrap());
+
+        let net: IpNetwork = Network("2001:db8:1:3::".parse().unwrap(), 128);
+        assert_eq!(net.stable_privacy_address(b"eth0", b"home", 0, b"secret"), None);
+        assert_eq!(net.stable_privacy_address(b"eth0", b"home", 255, b"secret"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_temporary_address() {
+        use rand::{SeedableRng, XorShiftRng};
+        use super::IpNetwork;
+
+        let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+        let net: IpNetwork = Network("2001:db8:1:2::".parse().unwrap(), 64);
+
+        let a = net.temporary_address(&mut rng);
+        let b = net.temporary_address(&mut rng);
+        assert!(net.contains(a) && net.contains(b));
+        assert!(a != b);
+        assert!(!a.has_reserved_interface_identifier());
+    }
+
+    #[test]
+    fn test_prefix_multicast() {
+        use addr::ipv6::MulticastScope::{Global, SiteLocal};
+
+        let net = Network("3ffe:ffff:1::".parse::<IpAddr>().unwrap(), 48);
+        assert_eq!(net.unicast_prefix_multicast(Global, 0x12345),
+                   Some("ff3e:30:3ffe:ffff:1:0:1:2345".parse().unwrap()));
+        assert_eq!(net.unicast_prefix_multicast(SiteLocal, 1),
+                   Some("ff35:30:3ffe:ffff:1::1".parse().unwrap()));
+        assert_eq!(Network(net.address(), 65).unicast_prefix_multicast(Global, 1), None);
+
+        let net = Network("2001:db8:beef:feed::".parse::<IpAddr>().unwrap(), 64);
+        let group = net.embedded_rp_multicast(7, Global, 0x1234).unwrap();
+        assert_eq!(group, "ff7e:740:2001:db8:beef:feed:0:1234".parse().unwrap());
+        assert_eq!(group.rendezvous_point(), Some("2001:db8:beef:feed::7".parse().unwrap()));
+        assert_eq!(net.embedded_rp_multicast(16, Global, 0x1234), None);
+        assert_eq!(Network(net.address(), 0).embedded_rp_multicast(7, Global, 0x1234), None);
     }
 }
@@ -1,12 +1,22 @@
 //! Provide operations over IP networks.
+use std::cmp::Ordering;
 use std::fmt;
-use addr::{IpAddrVersion, IpAddr};
+use std::str::FromStr;
+use addr::{IpAddrVersion, IpAddr, Ipv4, Ipv6};
+#[cfg(feature = "rand")]
+use rand::Rng;
+use wire::WireError;
 pub use self::IpNetwork::*;
+pub use self::network::Network;
 
 pub mod ipv4;
 pub mod ipv6;
+pub mod network;
+mod permutation;
 
 /// Describe an IP network.
+///
+/// Networks of different versions are totally ordered: all IPv4 networks sort before IPv6 ones.
 #[derive(Copy, Clone, Show, PartialEq, Eq, PartialOrd,
             Ord, Hash, RustcEncodable, RustcDecodable)]
 pub enum IpNetwork {
@@ -63,9 +73,14 @@ impl IpNetwork {
         mirror!(*self, net => net.host_prefix())
     }
 
-    /// The total number of addresses in the network.
-    pub fn num_addresses(&self) -> uint {
-        mirror!(*self, net => net.num_addresses())
+    /// The total number of addresses in the network, as a 128-bits integer (see `ipv6::IpAddr::to_u128`).
+    ///
+    /// `None` is returned for the IPv6 `/0` network, whose 2^128 addresses can't be represented.
+    pub fn num_addresses(&self) -> Option<[u64; 2]> {
+        match *self {
+            Ipv4Network(ref net) => Some([0, net.num_addresses()]),
+            Ipv6Network(ref net) => net.num_addresses(),
+        }
     }
 
     /// Get the mask of the network.
@@ -73,6 +88,11 @@ impl IpNetwork {
         mirror!(ip: *self, net => net.mask())
     }
 
+    /// Get the host mask of the network, also known as wildcard mask.
+    pub fn hostmask(&self) -> IpAddr {
+        mirror!(ip: *self, net => net.hostmask())
+    }
+
     /// Get the hosts range this network have.
     pub fn range(&self) -> (IpAddr, IpAddr) {
         use addr::IpAddr::*;
@@ -108,21 +128,127 @@ impl IpNetwork {
         }
     }
 
+    /// `true` if this network is wholly contained in `other`.
+    pub fn is_subnet_of(&self, other: IpNetwork) -> bool {
+        match (*self, other) {
+            (Ipv4Network(ref neta), Ipv4Network(netb)) => neta.is_subnet_of(netb),
+            (Ipv6Network(ref neta), Ipv6Network(netb)) => neta.is_subnet_of(netb),
+            _ => false,
+        }
+    }
+
+    /// `true` if `other` is wholly contained in this network.
+    pub fn is_supernet_of(&self, other: IpNetwork) -> bool {
+        other.is_subnet_of(*self)
+    }
+
+    /// `true` if this network and `other` don't overlap, but one starts right after the other ends.
+    pub fn is_adjacent_to(&self, other: IpNetwork) -> bool {
+        match (*self, other) {
+            (Ipv4Network(ref neta), Ipv4Network(netb)) => neta.is_adjacent_to(netb),
+            (Ipv6Network(ref neta), Ipv6Network(netb)) => neta.is_adjacent_to(netb),
+            _ => false,
+        }
+    }
+
+    /// Get the network made of the addresses common to this network and `other`, if any.
+    pub fn intersection(&self, other: IpNetwork) -> Option<IpNetwork> {
+        match (*self, other) {
+            (Ipv4Network(ref neta), Ipv4Network(netb)) => neta.intersection(netb).map(Ipv4Network),
+            (Ipv6Network(ref neta), Ipv6Network(netb)) => neta.intersection(netb).map(Ipv6Network),
+            _ => None,
+        }
+    }
+
+    /// Compare the network addresses, then the prefix lengths, as Python's `ipaddress` does.
+    ///
+    /// IPv4 networks sort before IPv6 ones.
+    pub fn compare_networks(&self, other: &IpNetwork) -> Ordering {
+        match (*self, *other) {
+            (Ipv4Network(ref neta), Ipv4Network(ref netb)) => neta.compare_networks(netb),
+            (Ipv6Network(ref neta), Ipv6Network(ref netb)) => neta.compare_networks(netb),
+            (Ipv4Network(_), Ipv6Network(_)) => Ordering::Less,
+            (Ipv6Network(_), Ipv4Network(_)) => Ordering::Greater,
+        }
+    }
+
     /// Iterate over all addresses of this network.
     pub fn iter(&self) -> Hosts {
-        let (start, stop) = self.range();
-        Hosts {
-            state: start,
-            stop: stop,
+        match *self {
+            Ipv4Network(ref net) => Hosts::Ipv4Hosts(net.iter()),
+            Ipv6Network(ref net) => Hosts::Ipv6Hosts(net.iter()),
         }
     }
 
     /// Iterate over all usable hosts of this network.
     pub fn hosts_iter(&self) -> Hosts {
-        let (start, stop) = self.range();
-        Hosts {
-            state: start + 1,
-            stop: stop - 1,
+        match *self {
+            Ipv4Network(ref net) => Hosts::Ipv4Hosts(net.hosts_iter()),
+            Ipv6Network(ref net) => Hosts::Ipv6Hosts(net.hosts_iter()),
+        }
+    }
+
+    /// Iterate over every `step`-th address of this network, starting with the network address.
+    pub fn step_by(&self, step: uint) -> StepBy<Hosts> {
+        StepBy::new(self.iter(), step)
+    }
+
+    /// Iterate over all addresses of this network in a pseudo-random order.
+    ///
+    /// Every address is yielded exactly once, and the order only depends on `seed`.
+    pub fn shuffle_iter(&self, seed: u64) -> Shuffle {
+        match *self {
+            Ipv4Network(ref net) => Shuffle::Ipv4Shuffle(net.shuffle_iter(seed)),
+            Ipv6Network(ref net) => Shuffle::Ipv6Shuffle(net.shuffle_iter(seed)),
+        }
+    }
+}
+
+///! Random sampling inside a network, available with the `rand` feature.
+#[cfg(feature = "rand")]
+impl IpNetwork {
+    /// Pick a random address of this network, network and broadcast addresses included.
+    pub fn random_address<R: Rng>(&self, rng: &mut R) -> IpAddr {
+        mirror!(ip: *self, net => net.random_address(rng))
+    }
+
+    /// Pick a random usable host of this network, with the same semantics as `hosts_iter`.
+    pub fn random_host<R: Rng>(&self, rng: &mut R) -> IpAddr {
+        mirror!(ip: *self, net => net.random_host(rng))
+    }
+
+    /// Pick a random subnet of this network, with the specified prefix length.
+    ///
+    /// `None` is returned if `prefix` is shorter than this network prefix, or too long for its version.
+    pub fn random_subnet<R: Rng>(&self, prefix: uint, rng: &mut R) -> Option<IpNetwork> {
+        match *self {
+            Ipv4Network(ref net) => net.random_subnet(prefix, rng).map(Ipv4Network),
+            Ipv6Network(ref net) => net.random_subnet(prefix, rng).map(Ipv6Network),
+        }
+    }
+}
+
+///! Compact wire encoding (see `Network::write_wire`).
+impl IpNetwork {
+    /// Get the length of the wire encoding of this network, in bytes.
+    pub fn wire_len(&self) -> uint {
+        mirror!(*self, net => net.wire_len())
+    }
+
+    /// Write the wire encoding of this network at the start of `buf`, returning the number of
+    /// bytes written.
+    pub fn write_wire(&self, buf: &mut [u8]) -> Result<uint, WireError> {
+        mirror!(*self, net => net.write_wire(buf))
+    }
+
+    /// Read the wire encoding of a network of the specified version at the start of `buf`,
+    /// returning the network with the number of bytes read.
+    ///
+    /// The encoding doesn't carry the address version, which the context must provide.
+    pub fn read_wire(version: IpAddrVersion, buf: &[u8]) -> Result<(IpNetwork, uint), WireError> {
+        match version {
+            Ipv4 => Network::read_wire(buf).map(|(net, len)| (Ipv4Network(net), len)),
+            Ipv6 => Network::read_wire(buf).map(|(net, len)| (Ipv6Network(net), len)),
         }
     }
 }
@@ -133,33 +259,265 @@ impl fmt::String for IpNetwork {
     }
 }
 
+impl FromStr for IpNetwork {
+    fn from_str(s: &str) -> Option<IpNetwork> {
+        s.parse().map(Ipv4Network).or_else(|| s.parse().map(Ipv6Network))
+    }
+}
+
+/// Get the smallest list of networks covering exactly the addresses from `first` to `last`, both
+/// included (see `Network::summarize_range`).
+///
+/// `None` is returned if the addresses aren't of the same version.
+pub fn summarize_address_range(first: IpAddr, last: IpAddr) -> Option<Vec<IpNetwork>> {
+    use addr::IpAddr::*;
+
+    match (first, last) {
+        (Ipv4Addr(first), Ipv4Addr(last)) => {
+            Some(Network::summarize_range(first, last).into_iter().map(Ipv4Network).collect())
+        }
+        (Ipv6Addr(first), Ipv6Addr(last)) => {
+            Some(Network::summarize_range(first, last).into_iter().map(Ipv6Network).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Get the smallest list of networks covering exactly the addresses of `networks` (see
+/// `Network::collapse`).
+///
+/// The networks are returned sorted, IPv4 networks first.
+pub fn collapse_addresses(networks: &[IpNetwork]) -> Vec<IpNetwork> {
+    let mut ipv4 = Vec::new();
+    let mut ipv6 = Vec::new();
+    for net in networks.iter() {
+        match *net {
+            Ipv4Network(net) => ipv4.push(net),
+            Ipv6Network(net) => ipv6.push(net),
+        }
+    }
+
+    let ipv4 = Network::collapse(ipv4.as_slice()).into_iter().map(Ipv4Network);
+    let ipv6 = Network::collapse(ipv6.as_slice()).into_iter().map(Ipv6Network);
+    ipv4.chain(ipv6).collect()
+}
+
 #[derive(Copy, Clone)]
-pub struct Hosts {
-    state: IpAddr,
-    stop: IpAddr,
+pub enum Hosts {
+    Ipv4Hosts(ipv4::Hosts),
+    Ipv6Hosts(ipv6::Hosts),
+}
+
+impl Hosts {
+    /// Skip the next `n` addresses, in constant time.
+    ///
+    /// This shadows `Iterator::skip`, which would step over the addresses one by one.
+    pub fn skip(self, n: uint) -> Hosts {
+        match self {
+            Hosts::Ipv4Hosts(hosts) => Hosts::Ipv4Hosts(hosts.skip(n)),
+            Hosts::Ipv6Hosts(hosts) => Hosts::Ipv6Hosts(hosts.skip(n)),
+        }
+    }
 }
 
 impl Iterator for Hosts {
     type Item = IpAddr;
 
     fn next(&mut self) -> Option<IpAddr> {
-        if self.state <= self.stop {
-            let result = self.state;
-            self.state = self.state + 1;
-            Some(result)
-        } else {
-            None
+        use addr::IpAddr::*;
+
+        match *self {
+            Hosts::Ipv4Hosts(ref mut hosts) => hosts.next().map(Ipv4Addr),
+            Hosts::Ipv6Hosts(ref mut hosts) => hosts.next().map(Ipv6Addr),
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        match *self {
+            Hosts::Ipv4Hosts(ref hosts) => hosts.size_hint(),
+            Hosts::Ipv6Hosts(ref hosts) => hosts.size_hint(),
+        }
+    }
+
+    fn nth(&mut self, n: uint) -> Option<IpAddr> {
+        use addr::IpAddr::*;
+
+        match *self {
+            Hosts::Ipv4Hosts(ref mut hosts) => hosts.nth(n).map(Ipv4Addr),
+            Hosts::Ipv6Hosts(ref mut hosts) => hosts.nth(n).map(Ipv6Addr),
         }
     }
 }
 
 impl DoubleEndedIterator for Hosts {
     fn next_back(&mut self) -> Option<IpAddr> {
-        if self.stop >= self.state {
-            self.stop = self.stop - 1;
-            Some(self.stop)
+        use addr::IpAddr::*;
+
+        match *self {
+            Hosts::Ipv4Hosts(ref mut hosts) => hosts.next_back().map(Ipv4Addr),
+            Hosts::Ipv6Hosts(ref mut hosts) => hosts.next_back().map(Ipv6Addr),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum Shuffle {
+    Ipv4Shuffle(ipv4::Shuffle),
+    Ipv6Shuffle(ipv6::Shuffle),
+}
+
+impl Iterator for Shuffle {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        use addr::IpAddr::*;
+
+        match *self {
+            Shuffle::Ipv4Shuffle(ref mut hosts) => hosts.next().map(Ipv4Addr),
+            Shuffle::Ipv6Shuffle(ref mut hosts) => hosts.next().map(Ipv6Addr),
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        match *self {
+            Shuffle::Ipv4Shuffle(ref hosts) => hosts.size_hint(),
+            Shuffle::Ipv6Shuffle(ref hosts) => hosts.size_hint(),
+        }
+    }
+}
+
+/// Iterator yielding every `step`-th element of an address iterator, relying on its `nth`
+/// implementation to skip the elements in between.
+#[derive(Copy, Clone)]
+pub struct StepBy<I> {
+    iter: I,
+    step: uint,
+    first: bool,
+}
+
+impl<I: Iterator> StepBy<I> {
+    /// The provided step must be strictly positive.
+    pub fn new(iter: I, step: uint) -> StepBy<I> {
+        assert!(step > 0);
+        StepBy {
+            iter: iter,
+            step: step,
+            first: true,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for StepBy<I> {
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<<I as Iterator>::Item> {
+        if self.first {
+            self.first = false;
+            self.iter.next()
+        } else {
+            self.iter.nth(self.step - 1)
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let (lo, hi) = self.iter.size_hint();
+        let count = |n: uint| if self.first {
+            if n == 0 { 0 } else { (n - 1) / self.step + 1 }
         } else {
-            None
+            n / self.step
+        };
+        (count(lo), hi.map(count))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+    use addr::{ipv4, ipv6};
+    use super::{IpNetwork, Network, Ipv4Network, Ipv6Network};
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("10.1.0.0/16".parse(), Some(Ipv4Network(Network(ipv4::IpAddr(10, 1, 0, 0), 16))));
+        assert_eq!("2001:db8::/32".parse(),
+                   Some(Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32))));
+        assert_eq!("10.1.2.3/16".parse(), Some(Ipv4Network(Network(ipv4::IpAddr(10, 1, 2, 3), 16))));
+
+        for s in ["10.0.0.0", "10.0.0.0/", "10.0.0.0/33", "10.0.0.0/+8", "10.0.0.0/8/8", "::/129", "/8"].iter() {
+            assert_eq!(s.parse::<IpNetwork>(), None);
         }
     }
+
+    #[test]
+    fn test_wire() {
+        use addr::{Ipv4, Ipv6};
+
+        let net = Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32));
+        let mut buf = [0u8; 5];
+        assert_eq!(net.wire_len(), 5);
+        assert_eq!(net.write_wire(&mut buf), Ok(5));
+        assert_eq!(IpNetwork::read_wire(Ipv6, &buf), Ok((net, 5)));
+
+        let net = Ipv4Network(Network(ipv4::IpAddr(32, 1, 13, 184), 32));
+        assert_eq!(IpNetwork::read_wire(Ipv4, &buf), Ok((net, 5)));
+    }
+
+    #[test]
+    fn test_summarize_address_range() {
+        use addr::IpAddr::*;
+        use super::summarize_address_range;
+
+        let first = Ipv4Addr(ipv4::IpAddr(10, 0, 0, 0));
+        let last = Ipv4Addr(ipv4::IpAddr(10, 0, 2, 255));
+        assert_eq!(summarize_address_range(first, last),
+                   Some(vec![Ipv4Network(Network(ipv4::IpAddr(10, 0, 0, 0), 23)),
+                             Ipv4Network(Network(ipv4::IpAddr(10, 0, 2, 0), 24))]));
+
+        let last = Ipv6Addr(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0, 1));
+        assert_eq!(summarize_address_range(first, last), None);
+    }
+
+    #[test]
+    fn test_collapse_addresses() {
+        use super::collapse_addresses;
+
+        let doc = ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0);
+        let upper = ipv6::IpAddr(0x2001, 0xdb8, 0x8000, 0, 0, 0, 0, 0);
+        let networks = [Ipv6Network(Network(doc, 33)),
+                        Ipv4Network(Network(ipv4::IpAddr(10, 1, 0, 0), 16)),
+                        Ipv6Network(Network(upper, 33)),
+                        Ipv4Network(Network(ipv4::IpAddr(10, 0, 0, 0), 16))];
+        assert_eq!(collapse_addresses(&networks),
+                   vec![Ipv4Network(Network(ipv4::IpAddr(10, 0, 0, 0), 15)),
+                        Ipv6Network(Network(doc, 32))]);
+    }
+
+    #[test]
+    fn test_ord() {
+        let net1 = Ipv4Network(Network(ipv4::IpAddr(10, 0, 0, 0), 8));
+        let net2 = Ipv4Network(Network(ipv4::IpAddr(10, 0, 0, 0), 16));
+        let net3 = Ipv6Network(Network(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0, 0), 0));
+        let net4 = Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32));
+
+        assert_eq!(net2.compare_networks(&net1), Ordering::Greater);
+        assert_eq!(net2.compare_networks(&net3), Ordering::Less);
+
+        let mut nets: Vec<IpNetwork> = vec![net4, net2, net3, net1];
+        nets.sort();
+        assert_eq!(nets, vec![net1, net2, net3, net4]);
+    }
+
+    #[test]
+    fn test_relations() {
+        let net1 = Ipv4Network(Network(ipv4::IpAddr(10, 0, 0, 0), 8));
+        let net2 = Ipv4Network(Network(ipv4::IpAddr(10, 0, 0, 0), 16));
+        let net3 = Ipv6Network(Network(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0, 0), 0));
+
+        assert!(net2.is_subnet_of(net1));
+        assert!(net1.is_supernet_of(net2));
+        assert!(!net2.is_subnet_of(net3));
+        assert_eq!(net1.intersection(net2), Some(net2));
+        assert_eq!(net1.intersection(net3), None);
+        assert!(!net1.is_adjacent_to(net3));
+    }
 }
@@ -0,0 +1,628 @@
+//! Provide operations over IP networks, generic over the address version.
+use std::cmp::{self, Ordering};
+use std::fmt;
+use std::num::Int;
+use std::str::FromStr;
+use addr::{IpAddress, IpAddrVersion, Ipv4, Ipv6};
+use super::StepBy;
+use super::permutation::Permutation;
+use wire::WireError;
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+/// Describe an IP network, by an address and the length of its network prefix.
+///
+/// `net::ipv4::IpNetwork` and `net::ipv6::IpNetwork` are the two instances of this type.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
+pub struct Network<A>(pub A, pub uint);
+
+impl<A: IpAddress> Network<A> {
+    /// Create a network from an address and a prefix length.
+    ///
+    /// The provided prefix length must be in the prefixlen-range of the address version.
+    pub fn new(addr: A, prefix: uint) -> Network<A> {
+        assert!(prefix <= A::bit_width());
+        Network(addr, prefix)
+    }
+
+    /// Get the corresponding IP address version.
+    pub fn version(&self) -> IpAddrVersion {
+        let &Network(addr, _) = self;
+        addr.version()
+    }
+
+    /// Get the network address for the network.
+    pub fn address(&self) -> A {
+        let &Network(addr, _) = self;
+        addr & self.mask()
+    }
+
+    /// Get the broadcast address for the network.
+    pub fn broadcast_address(&self) -> A {
+        self.address() | !self.mask()
+    }
+
+    /// Get the length of the network prefix, in bits.
+    pub fn prefix(&self) -> uint {
+        let &Network(_, prefix) = self;
+        prefix
+    }
+
+    /// Get the length of the host prefix, in bits.
+    pub fn host_prefix(&self) -> uint {
+        A::bit_width() - self.prefix()
+    }
+
+    /// Get the mask of the network.
+    pub fn mask(&self) -> A {
+        A::with_prefixlen(self.prefix())
+    }
+
+    /// Get the host mask of the network, also known as wildcard mask: the complement of `mask`.
+    pub fn hostmask(&self) -> A {
+        !self.mask()
+    }
+
+    /// Get the hosts range this network have.
+    pub fn range(&self) -> (A, A) {
+        (self.address(), self.broadcast_address())
+    }
+
+    /// `true` if this ip is contained in the network.
+    pub fn contains(&self, ip: A) -> bool {
+        let (start, stop) = self.range();
+        start <= ip && ip <= stop
+    }
+
+    /// `true` if this network is partly or wholly contained in other or other is wholly contained in this network.
+    pub fn overlaps(&self, other: Network<A>) -> bool {
+        other.contains(self.address()) || other.contains(self.broadcast_address())
+        || self.contains(other.address()) || self.contains(other.broadcast_address())
+    }
+
+    /// `true` if this network is wholly contained in `other`.
+    pub fn is_subnet_of(&self, other: Network<A>) -> bool {
+        other.prefix() <= self.prefix() && other.contains(self.address())
+    }
+
+    /// `true` if `other` is wholly contained in this network.
+    pub fn is_supernet_of(&self, other: Network<A>) -> bool {
+        other.is_subnet_of(*self)
+    }
+
+    /// `true` if this network and `other` don't overlap, but one starts right after the other ends.
+    pub fn is_adjacent_to(&self, other: Network<A>) -> bool {
+        let follows = |a: &Network<A>, b: &Network<A>| {
+            a.broadcast_address().checked_add(1) == Some(b.address())
+        };
+        follows(self, &other) || follows(&other, self)
+    }
+
+    /// Get the network made of the addresses common to this network and `other`, if any.
+    ///
+    /// Two networks either are disjoint or one contains the other, so the intersection is
+    /// the smallest of both networks.
+    pub fn intersection(&self, other: Network<A>) -> Option<Network<A>> {
+        if self.is_subnet_of(other) {
+            Some(Network(self.address(), self.prefix()))
+        } else if other.is_subnet_of(*self) {
+            Some(Network(other.address(), other.prefix()))
+        } else {
+            None
+        }
+    }
+
+    /// Compare the network addresses, then the prefix lengths, as Python's `ipaddress` does:
+    /// a network sorts before its subnets sharing the same network address.
+    pub fn compare_networks(&self, other: &Network<A>) -> Ordering {
+        match self.address().cmp(&other.address()) {
+            Ordering::Equal => self.prefix().cmp(&other.prefix()),
+            ordering => ordering,
+        }
+    }
+
+    /// Iterate over all addresses of this network.
+    pub fn iter(&self) -> Hosts<A> {
+        let (start, stop) = self.range();
+        Hosts::new(start, stop)
+    }
+
+    /// Iterate over all usable hosts of this network.
+    ///
+    /// For IPv4, the network and broadcast addresses are excluded, except for `/31`
+    /// point-to-point networks (RFC 3021) and `/32` host routes, where every address is usable.
+    ///
+    /// IPv6 has no broadcast address, so only the Subnet-Router anycast address (the network
+    /// address) is excluded, except for `/127` point-to-point networks (RFC 6164) and `/128`
+    /// host routes.
+    pub fn hosts_iter(&self) -> Hosts<A> {
+        let (start, stop) = self.range();
+        if self.host_prefix() <= 1 {
+            return Hosts::new(start, stop);
+        }
+
+        let start = start.checked_add(1).unwrap();
+        match self.version() {
+            Ipv4 => Hosts::new(start, stop.checked_sub(1).unwrap()),
+            Ipv6 => Hosts::new(start, stop),
+        }
+    }
+
+    /// Iterate over every `step`-th address of this network, starting with the network address.
+    pub fn step_by(&self, step: uint) -> StepBy<Hosts<A>> {
+        StepBy::new(self.iter(), step)
+    }
+
+    /// Iterate over all addresses of this network in a pseudo-random order.
+    ///
+    /// Every address is yielded exactly once, and the order only depends on `seed`.
+    pub fn shuffle_iter(&self, seed: u64) -> Shuffle<A> {
+        Shuffle {
+            base: self.address(),
+            perm: Permutation::new(self.host_prefix(), seed),
+        }
+    }
+}
+
+///! Conversions from address ranges.
+impl<A: IpAddress> Network<A> {
+    /// Get the smallest list of networks covering exactly the addresses from `first` to `last`,
+    /// both included, as Python's `ipaddress.summarize_address_range` does.
+    ///
+    /// The list is empty if `first` is greater than `last`.
+    pub fn summarize_range(first: A, last: A) -> Vec<Network<A>> {
+        let mut networks = Vec::new();
+        let mut first = first;
+
+        while first <= last {
+            let mut bits = trailing_zeros(first);
+            let mut end = first | !A::with_prefixlen(A::bit_width() - bits);
+            while end > last {
+                bits -= 1;
+                end = first | !A::with_prefixlen(A::bit_width() - bits);
+            }
+
+            networks.push(Network(first, A::bit_width() - bits));
+            first = match end.checked_add(1) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        networks
+    }
+}
+
+///! Collapsing lists of networks.
+impl<A: IpAddress> Network<A> {
+    /// Get the smallest list of networks covering exactly the addresses of `networks`, as
+    /// Python's `ipaddress.collapse_addresses` does: overlapping and adjacent networks are merged.
+    ///
+    /// The networks are returned sorted, without host bits set.
+    pub fn collapse(networks: &[Network<A>]) -> Vec<Network<A>> {
+        let mut sorted = networks.to_vec();
+        sorted.sort_by(|a, b| a.compare_networks(b));
+
+        let mut collapsed = Vec::new();
+        let mut current: Option<(A, A)> = None;
+        for net in sorted.iter() {
+            let (first, last) = net.range();
+            current = match current {
+                Some((start, end)) if first <= end || end.checked_add(1) == Some(first) => {
+                    Some((start, cmp::max(end, last)))
+                }
+                Some((start, end)) => {
+                    collapsed.push_all(Network::summarize_range(start, end).as_slice());
+                    Some((first, last))
+                }
+                None => Some((first, last)),
+            };
+        }
+
+        if let Some((start, end)) = current {
+            collapsed.push_all(Network::summarize_range(start, end).as_slice());
+        }
+        collapsed
+    }
+}
+
+/// Get the number of trailing zero bits of an address, at most its bit width.
+fn trailing_zeros<A: IpAddress>(addr: A) -> uint {
+    let zeros = match addr.to_u128() {
+        [0, 0] => 128,
+        [hi, 0] => 64 + hi.trailing_zeros(),
+        [_, lo] => lo.trailing_zeros(),
+    };
+    cmp::min(zeros, A::bit_width())
+}
+
+///! Random sampling inside a network, available with the `rand` feature.
+#[cfg(feature = "rand")]
+impl<A: IpAddress> Network<A> {
+    /// Pick a random address of this network, network and broadcast addresses included.
+    pub fn random_address<R: Rng>(&self, rng: &mut R) -> A {
+        self.address() | (A::from_u128([rng.gen(), rng.gen()]) & !self.mask())
+    }
+
+    /// Pick a random usable host of this network, with the same semantics as `hosts_iter`.
+    pub fn random_host<R: Rng>(&self, rng: &mut R) -> A {
+        loop {
+            let ip = self.random_address(rng);
+            if self.is_usable_host(ip) {
+                return ip;
+            }
+        }
+    }
+
+    /// Pick a random subnet of this network, with the specified prefix length.
+    ///
+    /// `None` is returned if `prefix` is shorter than this network prefix, or too long for its version.
+    pub fn random_subnet<R: Rng>(&self, prefix: uint, rng: &mut R) -> Option<Network<A>> {
+        if prefix < self.prefix() || prefix > A::bit_width() {
+            return None;
+        }
+
+        let ip = self.random_address(rng) & A::with_prefixlen(prefix);
+        Some(Network(ip, prefix))
+    }
+
+    /// `true` if `ip` is yielded by `hosts_iter`.
+    fn is_usable_host(&self, ip: A) -> bool {
+        if self.host_prefix() <= 1 {
+            return self.contains(ip);
+        }
+
+        match self.version() {
+            Ipv4 => ip != self.address() && ip != self.broadcast_address(),
+            Ipv6 => ip != self.address(),
+        }
+    }
+}
+
+///! Compact wire encoding, as used by BGP NLRI (RFC 4271) and DHCP classless static routes
+///! (RFC 3442): the prefix length, followed by the significant octets of the network address.
+impl<A: IpAddress> Network<A> {
+    /// Get the length of the wire encoding of this network, in bytes.
+    pub fn wire_len(&self) -> uint {
+        1 + (self.prefix() + 7) / 8
+    }
+
+    /// Write the wire encoding of this network at the start of `buf`, returning the number of
+    /// bytes written.
+    pub fn write_wire(&self, buf: &mut [u8]) -> Result<uint, WireError> {
+        let len = self.wire_len();
+        if buf.len() < len {
+            return Err(WireError::BufferTooShort);
+        }
+
+        buf[0] = self.prefix() as u8;
+        let n = self.address().to_u128();
+        for (i, b) in buf[1..len].iter_mut().enumerate() {
+            *b = octet::<A>(n, i);
+        }
+        Ok(len)
+    }
+
+    /// Read the wire encoding of a network at the start of `buf`, returning the network with the
+    /// number of bytes read.
+    ///
+    /// The bits of the last octet beyond the prefix length must be zero.
+    pub fn read_wire(buf: &[u8]) -> Result<(Network<A>, uint), WireError> {
+        if buf.is_empty() {
+            return Err(WireError::Truncated);
+        }
+
+        let prefix = buf[0] as uint;
+        if prefix > A::bit_width() {
+            return Err(WireError::InvalidPrefixLen(prefix));
+        }
+
+        let len = 1 + (prefix + 7) / 8;
+        if buf.len() < len {
+            return Err(WireError::Truncated);
+        }
+
+        let mut n = [0u64; 2];
+        for (i, &b) in buf[1..len].iter().enumerate() {
+            set_octet::<A>(&mut n, i, b);
+        }
+
+        let addr = A::from_u128(n);
+        if addr & !A::with_prefixlen(prefix) != A::from_u128([0, 0]) {
+            return Err(WireError::TrailingBits);
+        }
+        Ok((Network(addr, prefix), len))
+    }
+}
+
+/// Get the `i`-th octet, most significant first, of an address stored as a 128-bits integer.
+fn octet<A: IpAddress>(n: [u64; 2], i: uint) -> u8 {
+    let shift = A::bit_width() - 8 * (i + 1);
+    if shift >= 64 {
+        (n[0] >> (shift - 64)) as u8
+    } else {
+        (n[1] >> shift) as u8
+    }
+}
+
+/// Set the `i`-th octet, most significant first, of an address stored as a 128-bits integer.
+fn set_octet<A: IpAddress>(n: &mut [u64; 2], i: uint, b: u8) {
+    let shift = A::bit_width() - 8 * (i + 1);
+    if shift >= 64 {
+        n[0] |= (b as u64) << (shift - 64);
+    } else {
+        n[1] |= (b as u64) << shift;
+    }
+}
+
+impl<A: IpAddress> PartialOrd for Network<A> {
+    fn partial_cmp(&self, other: &Network<A>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Networks are ordered with `compare_networks`, then by the address they have been built with,
+/// so that the ordering stays consistent with equality.
+impl<A: IpAddress> Ord for Network<A> {
+    fn cmp(&self, other: &Network<A>) -> Ordering {
+        let (&Network(a, _), &Network(b, _)) = (self, other);
+        match self.compare_networks(other) {
+            Ordering::Equal => a.cmp(&b),
+            ordering => ordering,
+        }
+    }
+}
+
+impl<A: IpAddress> fmt::String for Network<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.address(), self.prefix())
+    }
+}
+
+/// Parse a network written as `address/prefix`. As with `Network::new`, the address may have
+/// host bits set.
+impl<A: IpAddress + FromStr> FromStr for Network<A> {
+    fn from_str(s: &str) -> Option<Network<A>> {
+        let parts: Vec<&str> = s.split('/').collect();
+        if parts.len() != 2 {
+            return None;
+        }
+
+        let (addr, prefix) = (parts[0], parts[1]);
+        if prefix.is_empty() || prefix.len() > 3 || !prefix.chars().all(|c| c.is_digit(10)) {
+            return None;
+        }
+
+        match (addr.parse(), prefix.parse()) {
+            (Some(addr), Some(prefix)) if prefix <= A::bit_width() => Some(Network(addr, prefix)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Hosts<A> {
+    state: A,
+    stop: A,
+    done: bool,
+}
+
+impl<A: IpAddress> Hosts<A> {
+    fn new(start: A, stop: A) -> Hosts<A> {
+        Hosts {
+            state: start,
+            stop: stop,
+            done: start > stop,
+        }
+    }
+
+    /// Number of remaining addresses minus one, as a 128-bits integer.
+    fn remaining(&self) -> [u64; 2] {
+        let [shi, slo] = self.state.to_u128();
+        let [ehi, elo] = self.stop.to_u128();
+        if elo >= slo {
+            [ehi - shi, elo - slo]
+        } else {
+            [ehi - shi - 1, (!0 - slo) + elo + 1]
+        }
+    }
+
+    /// Skip the next `n` addresses, in constant time.
+    ///
+    /// This shadows `Iterator::skip`, which would step over the addresses one by one.
+    pub fn skip(mut self, n: uint) -> Hosts<A> {
+        if n > 0 {
+            self.nth(n - 1);
+        }
+        self
+    }
+}
+
+impl<A: IpAddress> Iterator for Hosts<A> {
+    type Item = A;
+
+    fn next(&mut self) -> Option<A> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.state;
+        if self.state == self.stop {
+            self.done = true;
+        } else {
+            self.state = self.state.checked_add(1).unwrap();
+        }
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        use std::uint;
+
+        if self.done {
+            return (0, Some(0));
+        }
+
+        match self.remaining() {
+            [0, n] if n < uint::MAX as u64 => {
+                let len = n as uint + 1;
+                (len, Some(len))
+            }
+            _ => (uint::MAX, None),
+        }
+    }
+
+    fn nth(&mut self, n: uint) -> Option<A> {
+        if self.done {
+            return None;
+        }
+
+        match self.remaining() {
+            [0, rem] if n as u64 > rem => {
+                self.done = true;
+                None
+            }
+            _ => {
+                self.state = self.state.checked_add(n as u64).unwrap();
+                self.next()
+            }
+        }
+    }
+}
+
+impl<A: IpAddress> DoubleEndedIterator for Hosts<A> {
+    fn next_back(&mut self) -> Option<A> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.stop;
+        if self.stop == self.state {
+            self.done = true;
+        } else {
+            self.stop = self.stop.checked_sub(1).unwrap();
+        }
+        Some(result)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Shuffle<A> {
+    base: A,
+    perm: Permutation,
+}
+
+impl<A: IpAddress> Iterator for Shuffle<A> {
+    type Item = A;
+
+    fn next(&mut self) -> Option<A> {
+        match self.perm.next() {
+            Some(n) => Some(self.base | A::from_u128(n)),
+            None => None,
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.perm.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Network;
+    use addr::{IpAddress, ipv4, ipv6};
+
+    fn last_host<A: IpAddress>(net: Network<A>) -> Option<A> {
+        net.hosts_iter().next_back()
+    }
+
+    #[test]
+    fn test_generic() {
+        let net = Network(ipv4::IpAddr(10, 0, 0, 0), 24);
+        assert_eq!(last_host(net), Some(ipv4::IpAddr(10, 0, 0, 254)));
+
+        let net = Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 120);
+        assert_eq!(last_host(net), Some(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0xff)));
+    }
+
+    #[test]
+    fn test_wire() {
+        use wire::WireError;
+
+        let mut buf = [0u8; 17];
+        let net = Network(ipv4::IpAddr(10, 1, 128, 0), 17);
+        assert_eq!(net.wire_len(), 4);
+        assert_eq!(net.write_wire(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], [17, 10, 1, 128].as_slice());
+        assert_eq!(Network::read_wire(&buf[..4]), Ok((net, 4)));
+        assert_eq!(net.write_wire(&mut buf[..3]), Err(WireError::BufferTooShort));
+
+        let net = Network(ipv4::IpAddr(192, 0, 2, 1), 24);
+        assert_eq!(net.write_wire(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], [24, 192, 0, 2].as_slice());
+
+        let net = Network(ipv4::IpAddr(0, 0, 0, 0), 0);
+        assert_eq!(net.write_wire(&mut buf), Ok(1));
+        assert_eq!(Network::read_wire(&[0, 42]), Ok((net, 1)));
+
+        let net = Network(ipv6::IpAddr(0x2001, 0xdb8, 0xcafe, 0, 0, 0, 0, 0), 48);
+        assert_eq!(net.write_wire(&mut buf), Ok(7));
+        assert_eq!(&buf[..7], [48, 0x20, 0x01, 0x0d, 0xb8, 0xca, 0xfe].as_slice());
+        assert_eq!(Network::read_wire(&buf[..7]), Ok((net, 7)));
+
+        let net = Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 128);
+        assert_eq!(net.write_wire(&mut buf), Ok(17));
+        assert_eq!(Network::read_wire(&buf), Ok((net, 17)));
+
+        let read = |buf: &[u8]| Network::<ipv4::IpAddr>::read_wire(buf);
+        assert_eq!(read(&[]), Err(WireError::Truncated));
+        assert_eq!(read(&[24, 192, 0]), Err(WireError::Truncated));
+        assert_eq!(read(&[33, 192, 0, 2, 1, 0]), Err(WireError::InvalidPrefixLen(33)));
+        assert_eq!(read(&[17, 10, 1, 192]), Err(WireError::TrailingBits));
+    }
+
+    #[test]
+    fn test_summarize_range() {
+        let summarize = |first: ipv4::IpAddr, last: ipv4::IpAddr| Network::summarize_range(first, last);
+
+        assert_eq!(summarize(ipv4::IpAddr(192, 0, 2, 0), ipv4::IpAddr(192, 0, 2, 255)),
+                   vec![Network(ipv4::IpAddr(192, 0, 2, 0), 24)]);
+        assert_eq!(summarize(ipv4::IpAddr(192, 0, 2, 1), ipv4::IpAddr(192, 0, 2, 6)),
+                   vec![Network(ipv4::IpAddr(192, 0, 2, 1), 32), Network(ipv4::IpAddr(192, 0, 2, 2), 31),
+                        Network(ipv4::IpAddr(192, 0, 2, 4), 31), Network(ipv4::IpAddr(192, 0, 2, 6), 32)]);
+        assert_eq!(summarize(ipv4::IpAddr(0, 0, 0, 0), ipv4::IpAddr(255, 255, 255, 255)),
+                   vec![Network(ipv4::IpAddr(0, 0, 0, 0), 0)]);
+        assert_eq!(summarize(ipv4::IpAddr(255, 255, 255, 255), ipv4::IpAddr(255, 255, 255, 255)),
+                   vec![Network(ipv4::IpAddr(255, 255, 255, 255), 32)]);
+        assert_eq!(summarize(ipv4::IpAddr(10, 0, 0, 1), ipv4::IpAddr(10, 0, 0, 0)), vec![]);
+
+        // 5120 addresses starting at 2.16.0.0, as found in RIR statistics files.
+        assert_eq!(summarize(ipv4::IpAddr(2, 16, 0, 0), ipv4::IpAddr(2, 16, 19, 255)),
+                   vec![Network(ipv4::IpAddr(2, 16, 0, 0), 20), Network(ipv4::IpAddr(2, 16, 16, 0), 22)]);
+
+        let first = ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0);
+        let last = ipv6::IpAddr(0x2001, 0xdb8, 0, 2, 0xffff, 0xffff, 0xffff, 0xffff);
+        assert_eq!(Network::summarize_range(first, last),
+                   vec![Network(first, 63), Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 2, 0, 0, 0, 0), 64)]);
+
+        let max = ipv6::IpAddr(0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff);
+        assert_eq!(Network::summarize_range(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0, 0), max),
+                   vec![Network(ipv6::IpAddr(0, 0, 0, 0, 0, 0, 0, 0), 0)]);
+    }
+
+    #[test]
+    fn test_collapse() {
+        let net = |c: u8, d: u8, prefix: uint| Network(ipv4::IpAddr(192, 0, c, d), prefix);
+
+        assert_eq!(Network::collapse(&[net(2, 128, 25), net(2, 0, 25)]), vec![net(2, 0, 24)]);
+        assert_eq!(Network::collapse(&[net(2, 0, 24), net(2, 7, 32), net(2, 0, 24)]),
+                   vec![net(2, 0, 24)]);
+        assert_eq!(Network::collapse(&[net(2, 0, 24), net(3, 0, 24), net(5, 0, 24)]),
+                   vec![net(2, 0, 23), net(5, 0, 24)]);
+        assert_eq!(Network::collapse(&[net(1, 0, 24), net(2, 0, 24), net(3, 5, 24)]),
+                   vec![net(1, 0, 24), net(2, 0, 23)]);
+        assert_eq!(Network::collapse(&[] as &[Network<ipv4::IpAddr>]), vec![]);
+
+        let all = Network(ipv4::IpAddr(0, 0, 0, 0), 0);
+        let broadcast = Network(ipv4::IpAddr(255, 255, 255, 255), 32);
+        assert_eq!(Network::collapse(&[all, broadcast]), vec![all]);
+    }
+}
@@ -0,0 +1,193 @@
+//! Seeded pseudo-random permutation over the integers `0 .. 2^n`, for `0 <= n <= 128`.
+//!
+//! The permutation is a balanced Feistel network over `2 * ceil(n / 2)` bits: when `n` is odd,
+//! values falling outside of the domain are re-encrypted ("cycle walking") until they fit, which
+//! takes less than two rounds on average. No state other than the current index is kept, so
+//! walking even a `/64` needs no allocation.
+//!
+//! 128-bits integers are represented as two `u64` (see `addr::ipv6::IpAddr::to_u128`).
+
+const ROUNDS: uint = 6;
+
+/// Iterate over the integers `0 .. 2^bits` in a seeded pseudo-random order.
+#[derive(Copy, Clone)]
+pub struct Permutation {
+    bits: uint,
+    half: uint,
+    keys: [u64; ROUNDS],
+    index: [u64; 2],
+    done: bool,
+}
+
+impl Permutation {
+    pub fn new(bits: uint, seed: u64) -> Permutation {
+        assert!(bits <= 128);
+
+        let mut state = seed;
+        let mut keys = [0; ROUNDS];
+        for key in keys.iter_mut() {
+            *key = splitmix64(&mut state);
+        }
+
+        Permutation {
+            bits: bits,
+            half: (bits + 1) / 2,
+            keys: keys,
+            index: [0, 0],
+            done: false,
+        }
+    }
+
+    /// `true` if `n` is lower than `2^self.bits`.
+    fn in_domain(&self, n: [u64; 2]) -> bool {
+        let [hi, lo] = n;
+        if self.bits == 128 {
+            true
+        } else if self.bits >= 64 {
+            hi >> (self.bits - 64) == 0
+        } else {
+            hi == 0 && lo >> self.bits == 0
+        }
+    }
+
+    fn round(&self, r: u64, round: uint) -> u64 {
+        let mut state = r ^ self.keys[round];
+        splitmix64(&mut state) & mask(self.half)
+    }
+
+    fn encrypt(&self, n: [u64; 2]) -> [u64; 2] {
+        let h = self.half;
+        let [hi, lo] = n;
+
+        let (mut left, mut right) = if h == 64 {
+            (hi, lo)
+        } else {
+            ((hi << (64 - h)) | (lo >> h), lo & mask(h))
+        };
+
+        for round in range(0, ROUNDS) {
+            let next = left ^ self.round(right, round);
+            left = right;
+            right = next;
+        }
+
+        if h == 64 {
+            [left, right]
+        } else {
+            [left >> (64 - h), (left << h) | right]
+        }
+    }
+
+    /// Map an index of the domain to its permuted value.
+    pub fn permute(&self, n: [u64; 2]) -> [u64; 2] {
+        if self.bits == 0 {
+            return n;
+        }
+
+        let mut n = self.encrypt(n);
+        while !self.in_domain(n) {
+            n = self.encrypt(n);
+        }
+        n
+    }
+}
+
+impl Iterator for Permutation {
+    type Item = [u64; 2];
+
+    fn next(&mut self) -> Option<[u64; 2]> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.permute(self.index);
+
+        let [mut hi, mut lo] = self.index;
+        lo = lo.wrapping_add(1);
+        if lo == 0 {
+            hi = hi.wrapping_add(1);
+        }
+        self.index = [hi, lo];
+        self.done = self.index == [0, 0] || !self.in_domain(self.index);
+
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        use std::uint;
+
+        if self.done {
+            (0, Some(0))
+        } else if self.bits < 64 && (1 << self.bits) - self.index[1] <= uint::MAX as u64 {
+            let len = ((1 << self.bits) - self.index[1]) as uint;
+            (len, Some(len))
+        } else {
+            (uint::MAX, None)
+        }
+    }
+}
+
+fn mask(bits: uint) -> u64 {
+    if bits >= 64 {
+        !0
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// SplitMix64 generator step, used both for the key schedule and as the round function.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Permutation;
+
+    #[test]
+    fn test_full_cycle() {
+        for bits in range(0u, 13) {
+            let mut seen = Vec::from_elem(1 << bits, false);
+            for n in Permutation::new(bits, 42) {
+                assert_eq!(n[0], 0);
+                assert!(!seen[n[1] as uint]);
+                seen[n[1] as uint] = true;
+            }
+            assert!(seen.iter().all(|&seen| seen));
+        }
+    }
+
+    #[test]
+    fn test_seed() {
+        let a: Vec<[u64; 2]> = Permutation::new(16, 1).take(8).collect();
+        let b: Vec<[u64; 2]> = Permutation::new(16, 1).take(8).collect();
+        let c: Vec<[u64; 2]> = Permutation::new(16, 2).take(8).collect();
+
+        assert_eq!(a, b);
+        assert!(a != c);
+
+        let d: Vec<[u64; 2]> = Permutation::new(16, !0).take(8).collect();
+        assert_eq!(d.len(), 8);
+    }
+
+    #[test]
+    fn test_wide() {
+        let perm = Permutation::new(128, 7);
+        let a = perm.permute([0, 1]);
+        let b = perm.permute([0, 2]);
+        assert!(a != b);
+
+        for n in Permutation::new(65, 7).take(1000) {
+            assert!(n[0] <= 1);
+        }
+
+        let mut perm = Permutation::new(128, 7);
+        perm.index = [!0, !0];
+        assert!(perm.next().is_some());
+        assert!(perm.next().is_none());
+    }
+}
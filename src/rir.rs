@@ -0,0 +1,293 @@
+//! Parse the statistics files published by the Regional Internet Registries
+//! (`delegated-<registry>-latest` and `delegated-<registry>-extended-latest`).
+//!
+//! Each record describes a block of AS numbers or addresses. IPv4 blocks are given as a start
+//! address and a number of addresses, which needn't be a power of two: they are converted to the
+//! exact list of networks covering them.
+use std::error::{Error, FromError};
+use std::fmt;
+use std::io::{Buffer, EndOfFile, IoError};
+use addr::{ipv4, ipv6};
+use asn::Asn;
+use net::{self, IpNetwork, Network};
+
+/// The registration status of a block.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub enum Status {
+    Allocated,
+    Assigned,
+    Available,
+    Reserved,
+}
+
+impl Status {
+    fn from_name(name: &str) -> Option<Status> {
+        match name {
+            "allocated" => Some(Status::Allocated),
+            "assigned" => Some(Status::Assigned),
+            "available" => Some(Status::Available),
+            "reserved" => Some(Status::Reserved),
+            _ => None,
+        }
+    }
+}
+
+/// The resources described by a record.
+#[derive(Clone, Show, PartialEq, Eq)]
+pub enum Resource {
+    /// A block of `count` AS numbers, starting from `first`.
+    Asn {
+        first: Asn,
+        count: u32,
+    },
+    /// A block of addresses, as the networks covering it.
+    Networks(Vec<IpNetwork>),
+}
+
+/// A record of a statistics file.
+#[derive(Clone, Show, PartialEq, Eq)]
+pub struct Record {
+    /// The registry which manages the block: `afrinic`, `apnic`, `arin`, `lacnic` or `ripencc`.
+    pub registry: String,
+    /// The ISO 3166 two-letter code of the country of the holder, if the block is registered.
+    pub country: Option<String>,
+    pub resource: Resource,
+    pub status: Status,
+    /// The identifier of the holder, shared by all its blocks in the extended files.
+    pub opaque_id: Option<String>,
+}
+
+impl Record {
+    /// Parse a record line.
+    ///
+    /// `None` is returned for malformed lines, including the version, summary and comment lines.
+    pub fn parse(line: &str) -> Option<Record> {
+        let fields: Vec<&str> = line.trim_right().split('|').collect();
+        if fields.len() < 7 {
+            return None;
+        }
+
+        let resource = match (fields[2], fields[4].parse::<u32>()) {
+            ("asn", Some(count)) if count > 0 => match fields[3].parse::<u32>() {
+                Some(first) if first.checked_add(count - 1).is_some() => Resource::Asn {
+                    first: Asn(first),
+                    count: count,
+                },
+                _ => return None,
+            },
+            ("ipv4", Some(count)) if count > 0 => {
+                let first = match fields[3].parse::<ipv4::IpAddr>() {
+                    Some(first) => first,
+                    None => return None,
+                };
+                let last = match first.to_u32().checked_add(count - 1) {
+                    Some(last) => ipv4::IpAddr::from_u32(last),
+                    None => return None,
+                };
+                let networks = Network::summarize_range(first, last);
+                Resource::Networks(networks.into_iter().map(net::Ipv4Network).collect())
+            }
+            ("ipv6", Some(prefix)) if prefix as uint <= ipv6::MAX_PREFIXLEN => {
+                let net = match fields[3].parse::<ipv6::IpAddr>() {
+                    Some(addr) => Network(addr, prefix as uint),
+                    None => return None,
+                };
+                let Network(addr, _) = net;
+                if addr != net.address() {
+                    return None;
+                }
+                Resource::Networks(vec![net::Ipv6Network(net)])
+            }
+            _ => return None,
+        };
+
+        let status = match Status::from_name(fields[6]) {
+            Some(status) => status,
+            None => return None,
+        };
+
+        let optional = |field: &str| if field.is_empty() { None } else { Some(field.to_string()) };
+        Some(Record {
+            registry: fields[0].to_string(),
+            country: optional(fields[1]),
+            resource: resource,
+            status: status,
+            opaque_id: fields.get(7).and_then(|field| optional(*field)),
+        })
+    }
+}
+
+/// Errors raised while reading a statistics file.
+#[derive(Clone, Show, PartialEq, Eq)]
+pub enum RirError {
+    /// The underlying reader failed.
+    Io(IoError),
+    /// The line, numbered from 1, isn't a valid record.
+    Malformed(uint),
+}
+
+impl Error for RirError {
+    fn description(&self) -> &str {
+        match *self {
+            RirError::Io(ref err) => err.description(),
+            RirError::Malformed(_) => "malformed record",
+        }
+    }
+}
+
+impl fmt::String for RirError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RirError::Io(ref err) => write!(f, "{}", err),
+            RirError::Malformed(line) => write!(f, "malformed record at line {}", line),
+        }
+    }
+}
+
+impl FromError<IoError> for RirError {
+    fn from_error(err: IoError) -> RirError {
+        RirError::Io(err)
+    }
+}
+
+/// Iterate over the records of a statistics file.
+pub fn records<B: Buffer>(reader: B) -> Records<B> {
+    Records {
+        reader: reader,
+        line: 0,
+        done: false,
+    }
+}
+
+/// Iterator over the records of a statistics file, created by `records`.
+///
+/// The version line, the summary lines and the comments are skipped. The iteration stops after
+/// the first error.
+pub struct Records<B> {
+    reader: B,
+    line: uint,
+    done: bool,
+}
+
+impl<B: Buffer> Records<B> {
+    fn read_next(&mut self) -> Result<Option<Record>, RirError> {
+        loop {
+            let line = match self.reader.read_line() {
+                Ok(line) => line,
+                Err(ref err) if err.kind == EndOfFile => return Ok(None),
+                Err(err) => return Err(RirError::Io(err)),
+            };
+            self.line += 1;
+
+            let line = line.as_slice().trim();
+            if line.is_empty() || line.starts_with("#") || is_header(line) {
+                continue;
+            }
+
+            return match Record::parse(line) {
+                Some(record) => Ok(Some(record)),
+                None => Err(RirError::Malformed(self.line)),
+            };
+        }
+    }
+}
+
+impl<B: Buffer> Iterator for Records<B> {
+    type Item = Result<Record, RirError>;
+
+    fn next(&mut self) -> Option<Result<Record, RirError>> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_next() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// `true` for the version line (starting with the format version) and the summary lines.
+fn is_header(line: &str) -> bool {
+    line.split('|').next().map_or(false, |version| version.parse::<f32>().is_some())
+    || line.ends_with("|summary")
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufferedReader, MemReader};
+    use addr::{ipv4, ipv6};
+    use asn::Asn;
+    use net::{Ipv4Network, Ipv6Network, Network};
+    use super::{records, Record, Resource, RirError, Status};
+
+    static EXTENDED: &'static str = "2|ripencc|1700000000|4|19830705|20231114|+0100
+ripencc|*|asn|*|1|summary
+ripencc|*|ipv4|*|2|summary
+ripencc|*|ipv6|*|1|summary
+# A comment.
+ripencc|FR|asn|2200|1|19930901|allocated|8f1c5e2a-7c3b-4b7d-a1f2-4f3c2b1a0e9d
+ripencc|FR|ipv4|2.16.0.0|5120|20100712|allocated|8f1c5e2a-7c3b-4b7d-a1f2-4f3c2b1a0e9d
+ripencc||ipv4|2.56.0.0|256||available|
+ripencc|DE|ipv6|2001:db8::|32|19990819|allocated|0c9b2f9e-4c1e-4e0f-9d27-6a1f3c9b8e70
+";
+
+    #[test]
+    fn test_records() {
+        let reader = BufferedReader::new(MemReader::new(EXTENDED.as_bytes().to_vec()));
+        let records: Vec<Record> = records(reader).map(|record| record.unwrap()).collect();
+        assert_eq!(records.len(), 4);
+
+        assert_eq!(records[0].registry.as_slice(), "ripencc");
+        assert_eq!(records[0].country, Some("FR".to_string()));
+        assert_eq!(records[0].resource, Resource::Asn { first: Asn(2200), count: 1 });
+        assert_eq!(records[0].opaque_id, Some("8f1c5e2a-7c3b-4b7d-a1f2-4f3c2b1a0e9d".to_string()));
+
+        assert_eq!(records[1].resource, Resource::Networks(vec![
+            Ipv4Network(Network(ipv4::IpAddr(2, 16, 0, 0), 20)),
+            Ipv4Network(Network(ipv4::IpAddr(2, 16, 16, 0), 22)),
+        ]));
+        assert_eq!(records[1].status, Status::Allocated);
+
+        assert_eq!(records[2].country, None);
+        assert_eq!(records[2].status, Status::Available);
+        assert_eq!(records[2].opaque_id, None);
+
+        assert_eq!(records[3].resource, Resource::Networks(vec![
+            Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32)),
+        ]));
+    }
+
+    #[test]
+    fn test_parse() {
+        let record = Record::parse("arin|US|ipv4|192.0.2.0|256|19930901|assigned").unwrap();
+        assert_eq!(record.opaque_id, None);
+        assert_eq!(record.status, Status::Assigned);
+
+        for line in ["arin|US|ipv4|192.0.2.0|0|19930901|assigned",
+                     "arin|US|ipv4|255.255.255.0|257|19930901|assigned",
+                     "arin|US|ipv4|192.0.2|256|19930901|assigned",
+                     "arin|US|ipv6|2001:db8::1|32|19930901|assigned",
+                     "arin|US|ipv6|2001:db8::|129|19930901|assigned",
+                     "arin|US|asn|4294967295|2|19930901|assigned",
+                     "arin|US|ipv4|192.0.2.0|256|19930901|transferred",
+                     "arin|US|ipv4|192.0.2.0|256"].iter() {
+            assert_eq!(Record::parse(*line), None);
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        let data = "2|arin|1700000000|2|19830705|20231114|-0500\narin|US|ipv4|192.0.2|256|19930901|assigned\n";
+        let reader = BufferedReader::new(MemReader::new(data.as_bytes().to_vec()));
+        let results: Vec<_> = records(reader).collect();
+        assert_eq!(results, vec![Err(RirError::Malformed(2))]);
+    }
+}
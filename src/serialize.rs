@@ -0,0 +1,319 @@
+//! Serde support, available with the `serde` feature.
+//!
+//! Addresses and networks are serialized as their canonical textual representation. Serde can't
+//! tell human-readable formats from binary ones, so binary formats opt in to a compact encoding by
+//! wrapping values in `Packed`: addresses are then serialized as their octets in network byte
+//! order, and networks as the octets of their address followed by their prefix length.
+//!
+//! Deserialization is strict: the string must describe exactly one value of the expected type,
+//! and networks must not have host bits set.
+use std::str::FromStr;
+use serde::{de, ser, Deserialize, Deserializer, Serialize};
+use addr::{self, IpAddress, ipv4, ipv6};
+use eui::{Eui48, Eui64};
+use net::{self, Network};
+
+/// A value serialized as packed bytes instead of text.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub struct Packed<T>(pub T);
+
+/// A type deserialized from its textual representation.
+trait FromText: Sized + FromStr {
+    fn from_text(s: &str) -> Option<Self> {
+        s.parse()
+    }
+}
+
+impl FromText for ipv4::IpAddr {}
+
+impl FromText for ipv6::IpAddr {}
+
+impl FromText for addr::IpAddr {}
+
+impl FromText for Eui48 {}
+
+impl FromText for Eui64 {}
+
+impl<A: IpAddress + FromStr> FromText for Network<A> {
+    fn from_text(s: &str) -> Option<Network<A>> {
+        s.parse().and_then(|Network(addr, prefix)| strict_network(addr, prefix))
+    }
+}
+
+impl FromText for net::IpNetwork {
+    fn from_text(s: &str) -> Option<net::IpNetwork> {
+        let ipv4: Option<net::ipv4::IpNetwork> = FromText::from_text(s);
+        let ipv6: Option<net::ipv6::IpNetwork> = FromText::from_text(s);
+        ipv4.map(net::Ipv4Network).or(ipv6.map(net::Ipv6Network))
+    }
+}
+
+/// A type with a packed binary representation.
+trait PackedBytes: Sized {
+    fn to_packed(&self) -> Vec<u8>;
+    fn from_packed(b: &[u8]) -> Option<Self>;
+}
+
+impl PackedBytes for ipv4::IpAddr {
+    fn to_packed(&self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn from_packed(b: &[u8]) -> Option<ipv4::IpAddr> {
+        ipv4::IpAddr::from_bytes(b).ok()
+    }
+}
+
+impl PackedBytes for ipv6::IpAddr {
+    fn to_packed(&self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn from_packed(b: &[u8]) -> Option<ipv6::IpAddr> {
+        ipv6::IpAddr::from_bytes(b).ok()
+    }
+}
+
+impl PackedBytes for addr::IpAddr {
+    fn to_packed(&self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn from_packed(b: &[u8]) -> Option<addr::IpAddr> {
+        addr::IpAddr::from_bytes(b).ok()
+    }
+}
+
+impl PackedBytes for Eui48 {
+    fn to_packed(&self) -> Vec<u8> {
+        let n = self.to_u64();
+        range(0u, 6).rev().map(|i| (n >> (8 * i)) as u8).collect()
+    }
+
+    fn from_packed(b: &[u8]) -> Option<Eui48> {
+        if b.len() != 6 {
+            return None;
+        }
+        Some(Eui48::from_u64(b.iter().fold(0, |n, byte| n << 8 | *byte as u64)))
+    }
+}
+
+impl PackedBytes for Eui64 {
+    fn to_packed(&self) -> Vec<u8> {
+        let n = self.to_u64();
+        range(0u, 8).rev().map(|i| (n >> (8 * i)) as u8).collect()
+    }
+
+    fn from_packed(b: &[u8]) -> Option<Eui64> {
+        if b.len() != 8 {
+            return None;
+        }
+        Some(Eui64::from_u64(b.iter().fold(0, |n, byte| n << 8 | *byte as u64)))
+    }
+}
+
+impl<A: IpAddress + PackedBytes> PackedBytes for Network<A> {
+    fn to_packed(&self) -> Vec<u8> {
+        let Network(addr, prefix) = *self;
+        let mut b = addr.to_packed();
+        b.push(prefix as u8);
+        b
+    }
+
+    fn from_packed(b: &[u8]) -> Option<Network<A>> {
+        if b.is_empty() {
+            return None;
+        }
+        let (addr, prefix) = (&b[..b.len() - 1], b[b.len() - 1]);
+        PackedBytes::from_packed(addr).and_then(|addr| strict_network(addr, prefix as uint))
+    }
+}
+
+impl PackedBytes for net::IpNetwork {
+    fn to_packed(&self) -> Vec<u8> {
+        match *self {
+            net::Ipv4Network(net) => net.to_packed(),
+            net::Ipv6Network(net) => net.to_packed(),
+        }
+    }
+
+    fn from_packed(b: &[u8]) -> Option<net::IpNetwork> {
+        match b.len() {
+            5 => PackedBytes::from_packed(b).map(net::Ipv4Network),
+            17 => PackedBytes::from_packed(b).map(net::Ipv6Network),
+            _ => None,
+        }
+    }
+}
+
+/// Create a network, only if its prefix length is valid and `addr` has no host bits set.
+fn strict_network<A: IpAddress>(addr: A, prefix: uint) -> Option<Network<A>> {
+    if prefix <= A::bit_width() && addr & !A::with_prefixlen(prefix) == A::from_u128([0, 0]) {
+        Some(Network(addr, prefix))
+    } else {
+        None
+    }
+}
+
+macro_rules! impl_serde(
+    ($($ty:ty),*) => ($(
+        impl Serialize for $ty {
+            fn visit<V: ser::Visitor>(&self, visitor: &mut V) -> Result<V::Value, V::Error> {
+                visitor.visit_str(format!("{}", self).as_slice())
+            }
+        }
+
+        impl Deserialize for $ty {
+            fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<$ty, D::Error> {
+                struct TextVisitor;
+
+                impl de::Visitor for TextVisitor {
+                    type Value = $ty;
+
+                    fn visit_str<E: de::Error>(&mut self, s: &str) -> Result<$ty, E> {
+                        let value: Option<$ty> = FromText::from_text(s);
+                        value.ok_or(de::Error::syntax_error())
+                    }
+                }
+
+                deserializer.visit(TextVisitor)
+            }
+        }
+
+        impl Serialize for Packed<$ty> {
+            fn visit<V: ser::Visitor>(&self, visitor: &mut V) -> Result<V::Value, V::Error> {
+                let Packed(ref value) = *self;
+                visitor.visit_bytes(value.to_packed().as_slice())
+            }
+        }
+
+        impl Deserialize for Packed<$ty> {
+            fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Packed<$ty>, D::Error> {
+                struct PackedVisitor;
+
+                impl de::Visitor for PackedVisitor {
+                    type Value = Packed<$ty>;
+
+                    fn visit_bytes<E: de::Error>(&mut self, b: &[u8]) -> Result<Packed<$ty>, E> {
+                        let value: Option<$ty> = PackedBytes::from_packed(b);
+                        value.map(Packed).ok_or(de::Error::syntax_error())
+                    }
+
+                    // Formats without a native bytes type write them as a sequence.
+                    fn visit_seq<V: de::SeqVisitor>(&mut self, mut visitor: V)
+                                                    -> Result<Packed<$ty>, V::Error> {
+                        let mut b = vec![];
+                        while let Some(byte) = try!(visitor.visit::<u8>()) {
+                            b.push(byte);
+                        }
+                        try!(visitor.end());
+                        self.visit_bytes(b.as_slice())
+                    }
+                }
+
+                deserializer.visit(PackedVisitor)
+            }
+        }
+    )*);
+);
+
+impl_serde!(ipv4::IpAddr, ipv6::IpAddr, addr::IpAddr,
+            net::ipv4::IpNetwork, net::ipv6::IpNetwork, net::IpNetwork,
+            Eui48, Eui64);
+
+#[cfg(test)]
+mod test {
+    use std::fmt;
+    use serde::{json, Deserialize, Serialize};
+    use addr::{self, ipv4, ipv6};
+    use eui::{Eui48, Eui64};
+    use net::{self, Network};
+    use super::Packed;
+
+    fn round_trip<T: Serialize + Deserialize + PartialEq + fmt::Show>(value: T, text: &str) {
+        let json = format!("\"{}\"", text);
+        assert_eq!(json::to_string(&value).unwrap(), json);
+        assert_eq!(json::from_str::<T>(json.as_slice()).ok(), Some(value));
+    }
+
+    #[test]
+    fn test_addresses() {
+        let ip = ipv4::IpAddr(192, 0, 2, 1);
+        round_trip(ip, "192.0.2.1");
+        round_trip(addr::Ipv4Addr(ip), "192.0.2.1");
+
+        let ip = ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        round_trip(ip, "2001:db8::1");
+        round_trip(addr::Ipv6Addr(ip), "2001:db8::1");
+
+        round_trip(Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd), "00-1B-77-49-54-FD");
+        let eui = Eui64(0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd);
+        round_trip(eui, "00-1B-77-FF-FE-49-54-FD");
+    }
+
+    #[test]
+    fn test_networks() {
+        let net = Network(ipv4::IpAddr(10, 1, 0, 0), 16);
+        round_trip(net, "10.1.0.0/16");
+        round_trip(net::Ipv4Network(net), "10.1.0.0/16");
+
+        let net = Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32);
+        round_trip(net, "2001:db8::/32");
+        round_trip(net::Ipv6Network(net), "2001:db8::/32");
+    }
+
+    fn packed_round_trip<T>(value: T, bytes: &[u8])
+        where Packed<T>: Serialize + Deserialize, T: Copy + PartialEq + fmt::Show
+    {
+        let json = json::to_string(&Packed(value)).unwrap();
+        assert_eq!(json::from_str::<Vec<u8>>(json.as_slice()).ok(), Some(bytes.to_vec()));
+        assert_eq!(json::from_str::<Packed<T>>(json.as_slice()).ok(), Some(Packed(value)));
+    }
+
+    #[test]
+    fn test_packed() {
+        let ip = ipv4::IpAddr(192, 0, 2, 1);
+        packed_round_trip(ip, &[192, 0, 2, 1]);
+        packed_round_trip(addr::Ipv4Addr(ip), &[192, 0, 2, 1]);
+
+        let ip = ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let octets = [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        packed_round_trip(ip, &octets);
+        packed_round_trip(addr::Ipv6Addr(ip), &octets);
+
+        let net = Network(ipv4::IpAddr(10, 1, 0, 0), 16);
+        packed_round_trip(net, &[10, 1, 0, 0, 16]);
+        packed_round_trip(net::Ipv4Network(net), &[10, 1, 0, 0, 16]);
+
+        let net = Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32);
+        let octets = [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32];
+        packed_round_trip(net, &octets);
+        packed_round_trip(net::Ipv6Network(net), &octets);
+
+        let eui = Eui48(0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd);
+        packed_round_trip(eui, &[0x00, 0x1b, 0x77, 0x49, 0x54, 0xfd]);
+        let eui = Eui64(0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd);
+        packed_round_trip(eui, &[0x00, 0x1b, 0x77, 0xff, 0xfe, 0x49, 0x54, 0xfd]);
+    }
+
+    #[test]
+    fn test_packed_strict() {
+        assert!(json::from_str::<Packed<ipv4::IpAddr>>("[192, 0, 2]").is_err());
+        assert!(json::from_str::<Packed<ipv4::IpAddr>>("\"192.0.2.1\"").is_err());
+        assert!(json::from_str::<Packed<addr::IpAddr>>("[192, 0, 2, 1, 0]").is_err());
+        assert!(json::from_str::<Packed<net::ipv4::IpNetwork>>("[10, 1, 2, 3, 16]").is_err());
+        assert!(json::from_str::<Packed<net::ipv4::IpNetwork>>("[10, 1, 0, 0, 33]").is_err());
+        assert!(json::from_str::<Packed<net::IpNetwork>>("[]").is_err());
+        assert!(json::from_str::<Packed<Eui48>>("[0, 27, 119, 73, 84]").is_err());
+    }
+
+    #[test]
+    fn test_strict() {
+        assert!(json::from_str::<ipv4::IpAddr>("\"192.0.2.256\"").is_err());
+        assert!(json::from_str::<ipv4::IpAddr>("\"2001:db8::1\"").is_err());
+        assert!(json::from_str::<net::ipv4::IpNetwork>("\"10.1.2.3/16\"").is_err());
+        assert!(json::from_str::<net::ipv4::IpNetwork>("\"10.1.0.0/33\"").is_err());
+        assert!(json::from_str::<net::IpNetwork>("\"2001:db8::1/32\"").is_err());
+        assert!(json::from_str::<addr::IpAddr>("[192, 0, 2, 1]").is_err());
+    }
+}
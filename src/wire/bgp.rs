@@ -0,0 +1,203 @@
+//! BGP NLRI prefixes (RFC 4271 and RFC 4760), for IPv4 and IPv6 unicast.
+//!
+//! Each prefix is encoded in the compact wire encoding (see `Network::write_wire`). With ADD-PATH
+//! (RFC 7911), it is preceded by a 4 octets path identifier.
+use std::marker::PhantomData;
+use addr::IpAddress;
+use net::Network;
+use super::WireError;
+
+/// A prefix of a NLRI field.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub struct Prefix<A> {
+    /// The ADD-PATH path identifier, if the session negotiated it.
+    pub path_id: Option<u32>,
+    pub network: Network<A>,
+}
+
+impl<A: IpAddress> Prefix<A> {
+    /// Create a prefix without path identifier.
+    pub fn new(network: Network<A>) -> Prefix<A> {
+        Prefix {
+            path_id: None,
+            network: network,
+        }
+    }
+
+    /// Create a prefix with an ADD-PATH path identifier.
+    pub fn with_path_id(network: Network<A>, path_id: u32) -> Prefix<A> {
+        Prefix {
+            path_id: Some(path_id),
+            network: network,
+        }
+    }
+
+    /// Get the length of the encoding of this prefix, in bytes.
+    pub fn encoded_len(&self) -> uint {
+        self.network.wire_len() + if self.path_id.is_some() { 4 } else { 0 }
+    }
+
+    /// Write the encoding of this prefix at the start of `buf`, returning the number of bytes
+    /// written.
+    ///
+    /// Networks with host bits set are rejected with `WireError::TrailingBits`.
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<uint, WireError> {
+        let Network(addr, _) = self.network;
+        if addr != self.network.address() {
+            return Err(WireError::TrailingBits);
+        }
+        if buf.len() < self.encoded_len() {
+            return Err(WireError::BufferTooShort);
+        }
+
+        let mut pos = 0;
+        if let Some(path_id) = self.path_id {
+            for (i, b) in buf[..4].iter_mut().enumerate() {
+                *b = (path_id >> (24 - 8 * i)) as u8;
+            }
+            pos += 4;
+        }
+        pos += try!(self.network.write_wire(&mut buf[pos..]));
+        Ok(pos)
+    }
+
+    /// Read the encoding of a prefix at the start of `buf`, with a path identifier if `add_path`
+    /// is set, returning the prefix with the number of bytes read.
+    pub fn read_from(buf: &[u8], add_path: bool) -> Result<(Prefix<A>, uint), WireError> {
+        let mut pos = 0;
+        let path_id = if add_path {
+            if buf.len() < 4 {
+                return Err(WireError::Truncated);
+            }
+            pos += 4;
+            Some(buf[..4].iter().fold(0u32, |n, &b| (n << 8) | b as u32))
+        } else {
+            None
+        };
+
+        let (network, len) = try!(Network::read_wire(&buf[pos..]));
+        let prefix = Prefix {
+            path_id: path_id,
+            network: network,
+        };
+        Ok((prefix, pos + len))
+    }
+}
+
+/// Get the length of the NLRI field encoding `prefixes`, in bytes.
+pub fn encoded_len<A: IpAddress>(prefixes: &[Prefix<A>]) -> uint {
+    prefixes.iter().map(|prefix| prefix.encoded_len()).fold(0, |a, b| a + b)
+}
+
+/// Write the NLRI field encoding `prefixes` at the start of `buf`, returning the number of bytes
+/// written.
+pub fn encode<A: IpAddress>(prefixes: &[Prefix<A>], buf: &mut [u8]) -> Result<uint, WireError> {
+    let mut pos = 0;
+    for prefix in prefixes.iter() {
+        pos += try!(prefix.write_to(&mut buf[pos..]));
+    }
+    Ok(pos)
+}
+
+/// Iterate over the prefixes of a NLRI field, with path identifiers if `add_path` is set.
+pub fn decode<A: IpAddress>(data: &[u8], add_path: bool) -> Nlri<A> {
+    Nlri {
+        data: data,
+        add_path: add_path,
+        marker: PhantomData,
+    }
+}
+
+/// Iterator over the prefixes of a NLRI field, created by `decode`.
+///
+/// The iteration stops after the first error.
+pub struct Nlri<'a, A> {
+    data: &'a [u8],
+    add_path: bool,
+    marker: PhantomData<A>,
+}
+
+impl<'a, A: IpAddress> Iterator for Nlri<'a, A> {
+    type Item = Result<Prefix<A>, WireError>;
+
+    fn next(&mut self) -> Option<Result<Prefix<A>, WireError>> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        match Prefix::read_from(self.data, self.add_path) {
+            Ok((prefix, len)) => {
+                self.data = &self.data[len..];
+                Some(Ok(prefix))
+            }
+            Err(err) => {
+                self.data = &[];
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use addr::{ipv4, ipv6};
+    use net::Network;
+    use wire::WireError;
+    use super::{decode, encode, encoded_len, Prefix};
+
+    #[test]
+    fn test_ipv4() {
+        let prefixes = [
+            Prefix::new(Network(ipv4::IpAddr(192, 0, 2, 0), 24)),
+            Prefix::new(Network(ipv4::IpAddr(10, 0, 0, 0), 8)),
+            Prefix::new(Network(ipv4::IpAddr(0, 0, 0, 0), 0)),
+        ];
+        let data = [24, 192, 0, 2, 8, 10, 0];
+
+        let mut buf = [0u8; 16];
+        assert_eq!(encoded_len(&prefixes), 7);
+        assert_eq!(encode(&prefixes, &mut buf), Ok(7));
+        assert_eq!(&buf[..7], data.as_slice());
+
+        let decoded: Vec<_> = decode(&data, false).collect();
+        assert_eq!(decoded, prefixes.iter().map(|&prefix| Ok(prefix)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ipv6_add_path() {
+        let prefixes = [
+            Prefix::with_path_id(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32), 1),
+            Prefix::with_path_id(Network(ipv6::IpAddr(0x2001, 0xdb8, 0x8000, 0, 0, 0, 0, 0), 33), 0x01020304),
+        ];
+        let data = [0, 0, 0, 1, 32, 0x20, 0x01, 0x0d, 0xb8,
+                    1, 2, 3, 4, 33, 0x20, 0x01, 0x0d, 0xb8, 0x80];
+
+        let mut buf = [0u8; 32];
+        assert_eq!(encode(&prefixes, &mut buf), Ok(data.len()));
+        assert_eq!(&buf[..data.len()], data.as_slice());
+
+        let decoded: Vec<_> = decode(&data, true).collect();
+        assert_eq!(decoded, prefixes.iter().map(|&prefix| Ok(prefix)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_errors() {
+        let last = |data: &[u8], add_path: bool| decode::<ipv4::IpAddr>(data, add_path).last().unwrap();
+
+        assert_eq!(last(&[24, 192, 0], false), Err(WireError::Truncated));
+        assert_eq!(last(&[0, 0, 1], true), Err(WireError::Truncated));
+        assert_eq!(last(&[0, 0, 0, 1, 24, 192], true), Err(WireError::Truncated));
+        assert_eq!(last(&[33, 192, 0, 2, 1, 0], false), Err(WireError::InvalidPrefixLen(33)));
+        assert_eq!(last(&[8, 10, 23, 192, 0, 3], false), Err(WireError::TrailingBits));
+
+        let mut nlri = decode::<ipv6::IpAddr>(&[129], false);
+        assert_eq!(nlri.next(), Some(Err(WireError::InvalidPrefixLen(129))));
+        assert_eq!(nlri.next(), None);
+
+        let mut buf = [0u8; 16];
+        let prefix = Prefix::new(Network(ipv4::IpAddr(192, 0, 2, 1), 24));
+        assert_eq!(prefix.write_to(&mut buf), Err(WireError::TrailingBits));
+        let prefix = Prefix::with_path_id(Network(ipv4::IpAddr(192, 0, 2, 0), 24), 7);
+        assert_eq!(prefix.write_to(&mut buf[..7]), Err(WireError::BufferTooShort));
+    }
+}
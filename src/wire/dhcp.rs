@@ -0,0 +1,121 @@
+//! DHCP classless static route option (RFC 3442), sent as option 121, and as option 249 to some
+//! older Microsoft clients.
+//!
+//! The option data is a list of routes, each encoded as the destination network in the compact
+//! wire encoding (see `Network::write_wire`) followed by the 4 octets of the router address.
+use addr::ipv4::IpAddr;
+use net::ipv4::IpNetwork;
+use net::Network;
+use super::WireError;
+
+/// The DHCP option code for classless static routes.
+pub const OPTION_CLASSLESS_STATIC_ROUTE: u8 = 121;
+
+/// The DHCP option code Microsoft clients used for classless static routes before option 121.
+pub const OPTION_MS_CLASSLESS_STATIC_ROUTE: u8 = 249;
+
+/// A static route: the destination network and the router to reach it through.
+pub type Route = (IpNetwork, IpAddr);
+
+/// Get the length of the option data encoding `routes`, in bytes.
+///
+/// Data longer than 255 bytes must be split over several option instances (RFC 3396).
+pub fn encoded_len(routes: &[Route]) -> uint {
+    routes.iter().map(|&(net, _)| net.wire_len() + 4).fold(0, |a, b| a + b)
+}
+
+/// Write the option data encoding `routes` at the start of `buf`, returning the number of bytes
+/// written.
+///
+/// Destination networks with host bits set are rejected with `WireError::TrailingBits`, as these
+/// bits would be silently lost.
+pub fn encode(routes: &[Route], buf: &mut [u8]) -> Result<uint, WireError> {
+    let mut pos = 0;
+    for &(net, router) in routes.iter() {
+        let Network(addr, _) = net;
+        if addr != net.address() {
+            return Err(WireError::TrailingBits);
+        }
+
+        pos += try!(net.write_wire(&mut buf[pos..]));
+        pos += try!(router.write_to(&mut buf[pos..]));
+    }
+    Ok(pos)
+}
+
+/// Create the option data encoding `routes`.
+pub fn encode_to_vec(routes: &[Route]) -> Result<Vec<u8>, WireError> {
+    let mut buf = Vec::from_elem(encoded_len(routes), 0u8);
+    try!(encode(routes, buf.as_mut_slice()));
+    Ok(buf)
+}
+
+/// Decode the routes of the option data.
+///
+/// Destination networks must have their bits beyond the prefix length cleared, and the data
+/// mustn't end in the middle of a route.
+pub fn decode(data: &[u8]) -> Result<Vec<Route>, WireError> {
+    let mut routes = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let (net, len) = try!(Network::read_wire(&data[pos..]));
+        pos += len;
+
+        if data.len() - pos < 4 {
+            return Err(WireError::Truncated);
+        }
+        let router = try!(IpAddr::from_bytes(&data[pos..pos + 4]));
+        pos += 4;
+
+        routes.push((net, router));
+    }
+    Ok(routes)
+}
+
+#[cfg(test)]
+mod test {
+    use addr::ipv4::IpAddr;
+    use net::Network;
+    use wire::WireError;
+    use super::{decode, encode, encode_to_vec, encoded_len};
+
+    #[test]
+    fn test_encode() {
+        let routes = [
+            (Network(IpAddr(0, 0, 0, 0), 0), IpAddr(10, 0, 0, 1)),
+            (Network(IpAddr(10, 0, 0, 0), 8), IpAddr(10, 0, 0, 1)),
+            (Network(IpAddr(10, 17, 0, 0), 16), IpAddr(10, 0, 0, 1)),
+            (Network(IpAddr(10, 27, 129, 0), 24), IpAddr(10, 0, 0, 1)),
+            (Network(IpAddr(10, 229, 0, 128), 25), IpAddr(10, 0, 0, 1)),
+            (Network(IpAddr(10, 198, 122, 47), 32), IpAddr(10, 0, 0, 1)),
+        ];
+        let data = [
+            0, 10, 0, 0, 1,
+            8, 10, 10, 0, 0, 1,
+            16, 10, 17, 10, 0, 0, 1,
+            24, 10, 27, 129, 10, 0, 0, 1,
+            25, 10, 229, 0, 128, 10, 0, 0, 1,
+            32, 10, 198, 122, 47, 10, 0, 0, 1,
+        ];
+
+        assert_eq!(encoded_len(&routes), data.len());
+        assert_eq!(encode_to_vec(&routes), Ok(data.to_vec()));
+        assert_eq!(decode(&data), Ok(routes.to_vec()));
+        assert_eq!(decode(&[]), Ok(vec![]));
+
+        let mut buf = [0u8; 10];
+        assert_eq!(encode(&routes, &mut buf), Err(WireError::BufferTooShort));
+
+        let routes = [(Network(IpAddr(10, 1, 2, 3), 16), IpAddr(10, 0, 0, 1))];
+        assert_eq!(encode_to_vec(&routes), Err(WireError::TrailingBits));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode(&[24, 192, 0, 2, 10, 0, 0]), Err(WireError::Truncated));
+        assert_eq!(decode(&[24, 192, 0]), Err(WireError::Truncated));
+        assert_eq!(decode(&[33, 192, 0, 2, 1, 0, 10, 0, 0, 1]), Err(WireError::InvalidPrefixLen(33)));
+        assert_eq!(decode(&[23, 192, 0, 3, 10, 0, 0, 1]), Err(WireError::TrailingBits));
+        assert_eq!(decode(&[0, 10, 0, 0, 1, 8]), Err(WireError::Truncated));
+    }
+}
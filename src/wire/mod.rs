@@ -0,0 +1,44 @@
+//! Binary, network-byte-order encodings of addresses and networks.
+use std::error::Error;
+use std::fmt;
+
+pub mod bgp;
+pub mod dhcp;
+pub mod mrt;
+
+/// Errors raised while encoding or decoding binary representations.
+#[derive(Copy, Clone, Show, PartialEq, Eq)]
+pub enum WireError {
+    /// The output buffer is too short to hold the encoding.
+    BufferTooShort,
+    /// The input ends in the middle of an encoded value.
+    Truncated,
+    /// The input length, in bytes, isn't the length of an address.
+    InvalidLength(uint),
+    /// The prefix length is longer than the address.
+    InvalidPrefixLen(uint),
+    /// Bits beyond the prefix length are set.
+    TrailingBits,
+}
+
+impl Error for WireError {
+    fn description(&self) -> &str {
+        match *self {
+            WireError::BufferTooShort => "buffer too short",
+            WireError::Truncated => "truncated input",
+            WireError::InvalidLength(_) => "invalid address length",
+            WireError::InvalidPrefixLen(_) => "invalid prefix length",
+            WireError::TrailingBits => "bits set beyond the prefix length",
+        }
+    }
+}
+
+impl fmt::String for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WireError::InvalidLength(n) => write!(f, "{}: {} bytes", self.description(), n),
+            WireError::InvalidPrefixLen(n) => write!(f, "{}: {}", self.description(), n),
+            _ => write!(f, "{}", self.description()),
+        }
+    }
+}
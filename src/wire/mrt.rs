@@ -0,0 +1,475 @@
+//! Streaming reader for MRT routing table dumps in the TABLE_DUMP_V2 format (RFC 6396 and
+//! RFC 8050), as published by RouteViews and RIPE RIS.
+//!
+//! Records are read one at a time, so dumps of any size can be processed from any `Reader`;
+//! compressed dumps must be decompressed first.
+use std::error::{Error, FromError};
+use std::fmt;
+use std::io::{EndOfFile, IoError, Reader};
+use addr::{self, ipv4, Ipv4, Ipv6};
+use asn::Asn;
+use net::IpNetwork;
+use super::WireError;
+
+const TABLE_DUMP_V2: u16 = 13;
+
+const PEER_INDEX_TABLE: u16 = 1;
+const RIB_IPV4_UNICAST: u16 = 2;
+const RIB_IPV6_UNICAST: u16 = 4;
+const RIB_IPV4_UNICAST_ADDPATH: u16 = 8;
+const RIB_IPV6_UNICAST_ADDPATH: u16 = 10;
+
+const ATTR_AS_PATH: u8 = 2;
+const ATTR_FLAG_EXTENDED_LENGTH: u8 = 0x10;
+
+const AS_SET: u8 = 1;
+const AS_SEQUENCE: u8 = 2;
+
+/// The maximum length of a record accepted by `RibReader`, in bytes, so that a corrupt header
+/// can't make it allocate gigabytes. The largest RIB records of full tables are far below it.
+pub const MAX_RECORD_LEN: uint = 16 * 1024 * 1024;
+
+/// Errors raised while reading a MRT dump.
+#[derive(Clone, Show, PartialEq, Eq)]
+pub enum MrtError {
+    /// The underlying reader failed.
+    Io(IoError),
+    /// A record is malformed or truncated.
+    Wire(WireError),
+    /// A record header announces a length longer than `MAX_RECORD_LEN`.
+    InvalidRecord(uint),
+}
+
+impl Error for MrtError {
+    fn description(&self) -> &str {
+        match *self {
+            MrtError::Io(ref err) => err.description(),
+            MrtError::Wire(ref err) => err.description(),
+            MrtError::InvalidRecord(_) => "MRT record too long",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            MrtError::Io(ref err) => Some(err as &Error),
+            MrtError::Wire(ref err) => Some(err as &Error),
+            MrtError::InvalidRecord(_) => None,
+        }
+    }
+}
+
+impl fmt::String for MrtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MrtError::Io(ref err) => write!(f, "{}", err),
+            MrtError::Wire(ref err) => write!(f, "malformed MRT record: {}", err),
+            MrtError::InvalidRecord(len) => write!(f, "{}: {} bytes", self.description(), len),
+        }
+    }
+}
+
+impl FromError<IoError> for MrtError {
+    fn from_error(err: IoError) -> MrtError {
+        MrtError::Io(err)
+    }
+}
+
+impl FromError<WireError> for MrtError {
+    fn from_error(err: WireError) -> MrtError {
+        MrtError::Wire(err)
+    }
+}
+
+/// A BGP peer of the collector, from the peer index table.
+#[derive(Copy, Clone, Show, PartialEq, Eq, Hash)]
+pub struct Peer {
+    pub bgp_id: ipv4::IpAddr,
+    pub address: addr::IpAddr,
+    pub asn: Asn,
+}
+
+/// A route of a RIB dump.
+#[derive(Clone, Show, PartialEq, Eq)]
+pub struct RibEntry {
+    /// The sequence number of the RIB record this entry belongs to.
+    pub sequence: u32,
+    pub network: IpNetwork,
+    /// The index of the peer which announced the route, in the peer index table.
+    pub peer_index: u16,
+    /// The time the route has been received, in seconds since the Unix epoch.
+    pub originated_time: u32,
+    /// The ADD-PATH path identifier (RFC 8050), if any.
+    pub path_id: Option<u32>,
+    /// The BGP path attributes of the route, undecoded.
+    pub attributes: Vec<u8>,
+}
+
+impl RibEntry {
+    /// Get the origin AS of the route, from its AS_PATH attribute (see `origin_as`).
+    pub fn origin_as(&self) -> Option<Asn> {
+        origin_as(self.attributes.as_slice())
+    }
+}
+
+/// Get the origin AS from BGP path attributes, with 4 octets AS numbers as in TABLE_DUMP_V2.
+///
+/// The origin is the last AS of the AS_PATH. `None` is returned if the attributes are malformed,
+/// if there is no AS_PATH, or if the path ends with an AS_SET of more than one AS.
+pub fn origin_as(attributes: &[u8]) -> Option<Asn> {
+    let mut pos = 0;
+    while pos < attributes.len() {
+        let header = match take(attributes, &mut pos, 2) {
+            Ok(header) => header,
+            Err(_) => return None,
+        };
+        let len_size = if header[0] & ATTR_FLAG_EXTENDED_LENGTH != 0 { 2 } else { 1 };
+        let len = match take(attributes, &mut pos, len_size) {
+            Ok(len) => be(len) as uint,
+            Err(_) => return None,
+        };
+        let value = match take(attributes, &mut pos, len) {
+            Ok(value) => value,
+            Err(_) => return None,
+        };
+
+        if header[1] == ATTR_AS_PATH {
+            return path_origin(value);
+        }
+    }
+    None
+}
+
+fn path_origin(path: &[u8]) -> Option<Asn> {
+    let mut origin = None;
+    let mut pos = 0;
+    while pos < path.len() {
+        let header = match take(path, &mut pos, 2) {
+            Ok(header) => header,
+            Err(_) => return None,
+        };
+        let segment = match take(path, &mut pos, 4 * header[1] as uint) {
+            Ok(segment) => segment,
+            Err(_) => return None,
+        };
+
+        origin = match (header[0], segment.len()) {
+            (AS_SEQUENCE, n) if n > 0 => Some(Asn(be(&segment[n - 4..]))),
+            (AS_SET, 4) => Some(Asn(be(segment))),
+            (AS_SEQUENCE, _) | (AS_SET, _) => None,
+            // Confederation segments only describe the path inside the local confederation.
+            _ => origin,
+        };
+    }
+    origin
+}
+
+/// Streaming reader of the unicast RIB entries of a TABLE_DUMP_V2 dump.
+///
+/// Records of other types and subtypes (multicast and generic RIBs, BGP4MP messages...) are
+/// skipped. The iteration stops after the first error.
+pub struct RibReader<R> {
+    reader: R,
+    peers: Vec<Peer>,
+    record: Vec<u8>,
+    pos: uint,
+    remaining: uint,
+    sequence: u32,
+    network: Option<IpNetwork>,
+    add_path: bool,
+    done: bool,
+}
+
+impl<R: Reader> RibReader<R> {
+    pub fn new(reader: R) -> RibReader<R> {
+        RibReader {
+            reader: reader,
+            peers: Vec::new(),
+            record: Vec::new(),
+            pos: 0,
+            remaining: 0,
+            sequence: 0,
+            network: None,
+            add_path: false,
+            done: false,
+        }
+    }
+
+    /// Get the peers of the last peer index table read, referenced by `RibEntry::peer_index`.
+    pub fn peers(&self) -> &[Peer] {
+        self.peers.as_slice()
+    }
+
+    /// Read the next record, returning its subtype, or `None` at the end of the dump.
+    fn read_record(&mut self) -> Result<Option<u16>, MrtError> {
+        loop {
+            let mut header = Vec::with_capacity(12);
+            match self.reader.push_at_least(12, 12, &mut header) {
+                Ok(_) => {}
+                Err(ref err) if err.kind == EndOfFile && header.is_empty() => return Ok(None),
+                Err(ref err) if err.kind == EndOfFile => return Err(MrtError::Wire(WireError::Truncated)),
+                Err(err) => return Err(MrtError::Io(err)),
+            }
+
+            let kind = be(&header[4..6]) as u16;
+            let subtype = be(&header[6..8]) as u16;
+            let len = be(&header[8..12]) as uint;
+            if len > MAX_RECORD_LEN {
+                return Err(MrtError::InvalidRecord(len));
+            }
+
+            self.record.clear();
+            match self.reader.push_at_least(len, len, &mut self.record) {
+                Ok(_) => {}
+                Err(ref err) if err.kind == EndOfFile => return Err(MrtError::Wire(WireError::Truncated)),
+                Err(err) => return Err(MrtError::Io(err)),
+            }
+
+            if kind == TABLE_DUMP_V2 {
+                return Ok(Some(subtype));
+            }
+        }
+    }
+
+    fn read_peer_index_table(&mut self) -> Result<(), WireError> {
+        let data = self.record.as_slice();
+        let mut pos = 4;
+        let view_len = be(try!(take(data, &mut pos, 2))) as uint;
+        pos += view_len;
+        let count = be(try!(take(data, &mut pos, 2)));
+
+        self.peers.clear();
+        for _ in range(0, count) {
+            let kind = try!(take(data, &mut pos, 1))[0];
+            let bgp_id = try!(ipv4::IpAddr::from_bytes(try!(take(data, &mut pos, 4))));
+            let address_len = if kind & 0x1 != 0 { 16 } else { 4 };
+            let address = try!(addr::IpAddr::from_bytes(try!(take(data, &mut pos, address_len))));
+            let asn_len = if kind & 0x2 != 0 { 4 } else { 2 };
+            let asn = be(try!(take(data, &mut pos, asn_len)));
+
+            self.peers.push(Peer {
+                bgp_id: bgp_id,
+                address: address,
+                asn: Asn(asn),
+            });
+        }
+        Ok(())
+    }
+
+    /// Start reading the entries of a RIB record.
+    fn read_rib_header(&mut self, subtype: u16) -> Result<(), WireError> {
+        let version = match subtype {
+            RIB_IPV4_UNICAST | RIB_IPV4_UNICAST_ADDPATH => Ipv4,
+            _ => Ipv6,
+        };
+        let data = self.record.as_slice();
+        let mut pos = 0;
+
+        self.sequence = be(try!(take(data, &mut pos, 4)));
+        let (network, len) = try!(IpNetwork::read_wire(version, &data[pos..]));
+        pos += len;
+        self.remaining = be(try!(take(data, &mut pos, 2))) as uint;
+
+        self.network = Some(network);
+        self.add_path = subtype == RIB_IPV4_UNICAST_ADDPATH || subtype == RIB_IPV6_UNICAST_ADDPATH;
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn read_entry(&mut self) -> Result<RibEntry, WireError> {
+        let data = self.record.as_slice();
+        let mut pos = self.pos;
+
+        let peer_index = be(try!(take(data, &mut pos, 2))) as u16;
+        let originated_time = be(try!(take(data, &mut pos, 4)));
+        let path_id = if self.add_path {
+            Some(be(try!(take(data, &mut pos, 4))))
+        } else {
+            None
+        };
+        let len = be(try!(take(data, &mut pos, 2))) as uint;
+        let attributes = try!(take(data, &mut pos, len)).to_vec();
+
+        self.pos = pos;
+        self.remaining -= 1;
+        Ok(RibEntry {
+            sequence: self.sequence,
+            network: self.network.unwrap(),
+            peer_index: peer_index,
+            originated_time: originated_time,
+            path_id: path_id,
+            attributes: attributes,
+        })
+    }
+
+    fn read_next(&mut self) -> Result<Option<RibEntry>, MrtError> {
+        while self.remaining == 0 {
+            match try!(self.read_record()) {
+                Some(PEER_INDEX_TABLE) => try!(self.read_peer_index_table()),
+                Some(subtype @ RIB_IPV4_UNICAST) | Some(subtype @ RIB_IPV6_UNICAST)
+                | Some(subtype @ RIB_IPV4_UNICAST_ADDPATH)
+                | Some(subtype @ RIB_IPV6_UNICAST_ADDPATH) => try!(self.read_rib_header(subtype)),
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+
+        let entry = try!(self.read_entry());
+        Ok(Some(entry))
+    }
+}
+
+impl<R: Reader> Iterator for RibReader<R> {
+    type Item = Result<RibEntry, MrtError>;
+
+    fn next(&mut self) -> Option<Result<RibEntry, MrtError>> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_next() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Take the next `len` bytes of `data`, advancing `pos`.
+fn take<'a>(data: &'a [u8], pos: &mut uint, len: uint) -> Result<&'a [u8], WireError> {
+    if *pos > data.len() || data.len() - *pos < len {
+        return Err(WireError::Truncated);
+    }
+
+    let bytes = &data[*pos..*pos + len];
+    *pos += len;
+    Ok(bytes)
+}
+
+/// Decode a big-endian integer of at most 4 octets.
+fn be(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |n, &b| (n << 8) | b as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::MemReader;
+    use addr::{self, ipv4, ipv6};
+    use asn::Asn;
+    use net::{Ipv4Network, Ipv6Network, Network};
+    use wire::WireError;
+    use super::{origin_as, MrtError, Peer, RibReader};
+
+    fn record(kind: u16, subtype: u16, body: &[u8]) -> Vec<u8> {
+        let len = body.len();
+        let mut record = vec![0x5e, 0x00, 0x00, 0x00, (kind >> 8) as u8, kind as u8,
+                              (subtype >> 8) as u8, subtype as u8,
+                              (len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+        record.push_all(body);
+        record
+    }
+
+    /// AS_PATH attribute made of an AS_SEQUENCE of 64496 and 64511.
+    static AS_PATH: [u8; 13] = [0x40, 2, 10, 2, 2, 0, 0, 0xfb, 0xf0, 0, 0, 0xfb, 0xff];
+
+    fn dump() -> Vec<u8> {
+        let mut dump = Vec::new();
+        dump.push_all(record(13, 1, &[
+            192, 0, 2, 1, 0, 4, b'v', b'i', b'e', b'w', 0, 2,
+            0x0, 192, 0, 2, 10, 192, 0, 2, 10, 0xfb, 0xf0,
+            0x3, 192, 0, 2, 11, 0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x0b,
+            0, 0, 0xfb, 0xf1,
+        ]).as_slice());
+        // A BGP4MP record, skipped.
+        dump.push_all(record(16, 4, &[0, 0, 0, 0]).as_slice());
+
+        let mut rib = vec![0, 0, 0, 0, 24, 198, 51, 100, 0, 2];
+        rib.push_all(&[0, 0, 0x5e, 0, 0, 1, 0, 13]);
+        rib.push_all(&AS_PATH);
+        rib.push_all(&[0, 1, 0x5e, 0, 0, 2, 0, 0]);
+        dump.push_all(record(13, 2, rib.as_slice()).as_slice());
+
+        dump.push_all(record(13, 10, &[
+            0, 0, 0, 1, 32, 0x20, 0x01, 0x0d, 0xb8, 0, 1,
+            0, 1, 0x5e, 0, 0, 3, 0, 0, 0, 7, 0, 0,
+        ]).as_slice());
+        dump
+    }
+
+    #[test]
+    fn test_reader() {
+        let mut reader = RibReader::new(MemReader::new(dump()));
+
+        let entry = reader.next().unwrap().unwrap();
+        assert_eq!(entry.network, Ipv4Network(Network(ipv4::IpAddr(198, 51, 100, 0), 24)));
+        assert_eq!(entry.peer_index, 0);
+        assert_eq!(entry.originated_time, 0x5e000001);
+        assert_eq!(entry.path_id, None);
+        assert_eq!(entry.attributes, AS_PATH.to_vec());
+        assert_eq!(entry.origin_as(), Some(Asn(64511)));
+
+        assert_eq!(reader.peers(), [
+            Peer {
+                bgp_id: ipv4::IpAddr(192, 0, 2, 10),
+                address: addr::Ipv4Addr(ipv4::IpAddr(192, 0, 2, 10)),
+                asn: Asn(64496),
+            },
+            Peer {
+                bgp_id: ipv4::IpAddr(192, 0, 2, 11),
+                address: addr::Ipv6Addr(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0xb)),
+                asn: Asn(64497),
+            },
+        ].as_slice());
+
+        let entry = reader.next().unwrap().unwrap();
+        assert_eq!(entry.peer_index, 1);
+        assert_eq!(entry.origin_as(), None);
+
+        let entry = reader.next().unwrap().unwrap();
+        assert_eq!(entry.sequence, 1);
+        assert_eq!(entry.network, Ipv6Network(Network(ipv6::IpAddr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32)));
+        assert_eq!(entry.path_id, Some(7));
+
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut data = dump();
+        data.pop();
+        let entries: Vec<_> = RibReader::new(MemReader::new(data)).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2], Err(MrtError::Wire(WireError::Truncated)));
+
+        let mut data = dump();
+        let len = data.len();
+        data[len - 1] = 1;
+        let entries: Vec<_> = RibReader::new(MemReader::new(data)).collect();
+        assert_eq!(entries[2], Err(MrtError::Wire(WireError::Truncated)));
+
+        let mut data = record(13, 2, &[]);
+        data[8] = 0xff;
+        let entries: Vec<_> = RibReader::new(MemReader::new(data)).collect();
+        assert_eq!(entries, vec![Err(MrtError::InvalidRecord(0xff000000))]);
+    }
+
+    #[test]
+    fn test_origin_as() {
+        // AS_SEQUENCE 64496, then AS_SET {64500}, with an extended length.
+        let attributes = [0x50, 2, 0, 12, 2, 1, 0, 0, 0xfb, 0xf0, 1, 1, 0, 0, 0xfb, 0xf4];
+        assert_eq!(origin_as(&attributes), Some(Asn(64500)));
+
+        // ORIGIN attribute, then an AS_SEQUENCE followed by a confederation segment.
+        let attributes = [0x40, 1, 1, 0, 0x40, 2, 12, 2, 1, 0, 0, 0xfb, 0xf0, 3, 1, 0, 0, 0xfd, 0xe8];
+        assert_eq!(origin_as(&attributes), Some(Asn(64496)));
+
+        assert_eq!(origin_as(&[0x40, 2, 10, 1, 2, 0, 0, 0xfb, 0xf0, 0, 0, 0xfb, 0xf1]), None);
+        assert_eq!(origin_as(&[0x40, 1, 1, 0]), None);
+        assert_eq!(origin_as(&[]), None);
+    }
+}